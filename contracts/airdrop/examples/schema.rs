@@ -2,6 +2,7 @@ use std::env::current_dir;
 use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
+use ownership::PendingOwnerResponse;
 
 use airdrop::msg::{MemberResponse, ExecuteMsg, InstantiateMsg, QueryMsg, MigrateMsg, MemberListResponse};
 use airdrop::state::Config as ConfigResponse;
@@ -20,6 +21,7 @@ fn main() {
 
     export_schema(&schema_for!(ConfigResponse), &out_dir);
     export_schema(&schema_for!(StateResponse), &out_dir);
+    export_schema(&schema_for!(PendingOwnerResponse), &out_dir);
     export_schema(&schema_for!(MemberResponse), &out_dir);
     export_schema(&schema_for!(MemberListResponse), &out_dir);
 }
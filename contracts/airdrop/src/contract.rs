@@ -4,12 +4,13 @@ use cosmwasm_std::{Addr, BankMsg, Binary, Coin, Deps, DepsMut, Env, MessageInfo,
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cw0::{maybe_addr, must_pay};
-use cw2::{get_contract_version, set_contract_version};
+use cw2::set_contract_version;
 use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg};
 use cw_storage_plus::Bound;
+use ownership::{accept_ownership, cancel_ownership_proposal, check_migration, propose_new_owner, query_pending_owner};
 
 use platform_registry::{AddressBaseInfoResponse, PlatformRegistryQueryMsg};
-use staking::msg::MemberResponse as StakingMemberResponse;
+use staking::msg::StakerResponse as StakingStakerResponse;
 use staking::msg::QueryMsg as StakingQueryMsg;
 
 use crate::error::ContractError;
@@ -47,9 +48,9 @@ pub fn instantiate(
 fn mission_smart_contracts_from(deps: &DepsMut, m: Option<InstantiateMissionSmartContracts>) -> StdResult<MissionSmartContracts> {
     let res = match m {
         Some(m) => MissionSmartContracts {
-            lp_staking: option_addr_validate(&deps, &m.lp_staking)?,
-            tland_staking: option_addr_validate(&deps, &m.tland_staking)?,
-            platform_registry: option_addr_validate(&deps, &m.platform_registry)?,
+            lp_staking: option_addr_validate(deps, &m.lp_staking)?,
+            tland_staking: option_addr_validate(deps, &m.tland_staking)?,
+            platform_registry: option_addr_validate(deps, &m.platform_registry)?,
         },
         None => MissionSmartContracts {
             lp_staking: None,
@@ -62,7 +63,7 @@ fn mission_smart_contracts_from(deps: &DepsMut, m: Option<InstantiateMissionSmar
 
 fn option_addr_validate(deps: &DepsMut, value: &Option<String>) -> StdResult<Option<Addr>> {
     let v = match value {
-        Some(str) => Some(deps.api.addr_validate(&str)?),
+        Some(str) => Some(deps.api.addr_validate(str)?),
         None => None,
     };
     Ok(v)
@@ -70,13 +71,13 @@ fn option_addr_validate(deps: &DepsMut, value: &Option<String>) -> StdResult<Opt
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
-    let version = get_contract_version(deps.storage)?;
-    if version.contract != CONTRACT_NAME {
-        return Err(ContractError::CannotMigrate {
-            previous_contract: version.contract,
-        });
-    }
-    Ok(Response::default())
+    let previous = check_migration(deps.storage, &[CONTRACT_NAME], CONTRACT_VERSION)?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("previous_version", previous.version.to_string())
+        .add_attribute("new_version", CONTRACT_VERSION))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -87,8 +88,17 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::UpdateConfig { owner, fee_config, mission_smart_contracts } =>
-            execute_update_config(deps, env, info, owner, fee_config, mission_smart_contracts),
+        ExecuteMsg::UpdateConfig { fee_config, mission_smart_contracts } =>
+            execute_update_config(deps, env, info, fee_config, mission_smart_contracts),
+        ExecuteMsg::ProposeNewOwner { owner, expiry } => {
+            let cfg = CONFIG.load(deps.storage)?;
+            Ok(propose_new_owner(deps, env, info, &cfg.owner, owner, expiry)?)
+        }
+        ExecuteMsg::AcceptOwnership {} => execute_accept_ownership(deps, env, info),
+        ExecuteMsg::CancelOwnershipProposal {} => {
+            let cfg = CONFIG.load(deps.storage)?;
+            Ok(cancel_ownership_proposal(deps, info, &cfg.owner)?)
+        }
         ExecuteMsg::RegisterMembers(members) =>
             execute_register_members(deps, env, info, members),
         ExecuteMsg::RemoveMembers(addresses) =>
@@ -105,7 +115,6 @@ pub fn execute_update_config(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    new_owner: Option<String>,
     new_fee_config: Option<Vec<FeeConfig>>,
     new_mission_smart_contracts: Option<InstantiateMissionSmartContracts>,
 ) -> Result<Response, ContractError> {
@@ -115,14 +124,9 @@ pub fn execute_update_config(
         return Err(ContractError::Unauthorized {});
     }
 
-    let api = deps.api;
     let new_mission_sc = mission_smart_contracts_from(&deps, new_mission_smart_contracts)?;
 
     CONFIG.update(deps.storage, |mut existing_config| -> StdResult<_> {
-        // update new owner if set
-        if let Some(addr) = new_owner {
-            existing_config.owner = api.addr_validate(&addr)?;
-        }
         if let Some(fee_config) = new_fee_config {
             existing_config.fee_config = fee_config;
         }
@@ -146,6 +150,22 @@ pub fn execute_update_config(
         .add_attribute("sender", info.sender))
 }
 
+pub fn execute_accept_ownership(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let new_owner = accept_ownership(deps.storage, &env, &info.sender)?;
+    CONFIG.update(deps.storage, |mut existing_config| -> StdResult<_> {
+        existing_config.owner = new_owner;
+        Ok(existing_config)
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "accept_ownership")
+        .add_attribute("owner", info.sender))
+}
+
 pub fn execute_register_members(
     deps: DepsMut,
     _env: Env,
@@ -264,7 +284,7 @@ pub fn execute_claim(
 }
 
 fn calc_claim_amount(missions: &Missions, member: &Member) -> StdResult<Uint128> {
-    let passed_missions_num = calc_missions_passed(&missions);
+    let passed_missions_num = calc_missions_passed(missions);
 
     // amount earned equals amount multiplied by percentage of passed missions
     let amount_earned = member.amount
@@ -370,6 +390,7 @@ fn must_pay_fee(info: &MessageInfo, cfg: &Config, operation: String) -> Result<(
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::PendingOwner {} => to_binary(&query_pending_owner(deps)?),
         QueryMsg::State {} => to_binary(&query_state(deps)?),
         QueryMsg::Member { address } => to_binary(&query_member(deps, address)?),
         QueryMsg::ListMembers { start_after, limit } =>
@@ -378,11 +399,11 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
 }
 
 pub fn query_config(deps: Deps) -> StdResult<Config> {
-    Ok(CONFIG.load(deps.storage)?)
+    CONFIG.load(deps.storage)
 }
 
 pub fn query_state(deps: Deps) -> StdResult<State> {
-    Ok(STATE.load(deps.storage)?)
+    STATE.load(deps.storage)
 }
 
 pub fn query_member(deps: Deps, addr: String) -> StdResult<MemberResponse> {
@@ -451,11 +472,11 @@ fn check_missions(querier: &QuerierWrapper, cfg: &Config, addr: &Addr) -> StdRes
     if let Some(contract_addr) = cfg.mission_smart_contracts.lp_staking.clone() {
         let query = WasmQuery::Smart {
             contract_addr: contract_addr.to_string(),
-            msg: to_binary(&StakingQueryMsg::Member {
+            msg: to_binary(&StakingQueryMsg::Staker {
                 address: addr.to_string(),
             })?,
         }.into();
-        let res: StakingMemberResponse = querier.query(&query)?;
+        let res: StakingStakerResponse = querier.query(&query)?;
         if res.member.is_some() {
             missions.is_in_lp_staking = true;
         }
@@ -494,5 +515,47 @@ fn calc_missions_passed(missions: &Missions) -> u32 {
         passed += 1;
     }
 
-    return passed;
+    passed
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use cw2::{get_contract_version, set_contract_version};
+    use ownership::MigrationError;
+
+    use super::*;
+
+    #[test]
+    fn migrate_version() {
+        let mut deps = mock_dependencies(&[]);
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "0.1.0").unwrap();
+
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(res.attributes[1].value, "0.1.0");
+        assert_eq!(get_contract_version(&deps.storage).unwrap().version, CONTRACT_VERSION);
+
+        set_contract_version(&mut deps.storage, "crates.io:vesting", "0.1.0").unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Migration(MigrationError::CannotMigrate {
+                previous_contract: "crates.io:vesting".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn refuse_downgrade() {
+        let mut deps = mock_dependencies(&[]);
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "99.0.0").unwrap();
+
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Migration(MigrationError::CannotMigrateVersion {
+                previous_version: "99.0.0".to_string()
+            })
+        );
+    }
 }
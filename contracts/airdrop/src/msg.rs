@@ -1,4 +1,5 @@
 use cosmwasm_std::Uint128;
+use cw0::Expiration;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use crate::state::FeeConfig;
@@ -22,10 +23,15 @@ pub struct InstantiateMissionSmartContracts {
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
     UpdateConfig {
-        owner: Option<String>,
         fee_config: Option<Vec<FeeConfig>>,
         mission_smart_contracts: Option<InstantiateMissionSmartContracts>,
     },
+    ProposeNewOwner {
+        owner: String,
+        expiry: Option<Expiration>,
+    },
+    AcceptOwnership {},
+    CancelOwnershipProposal {},
     Claim {},
     RegisterMembers (
         Vec<RegisterMemberItem>
@@ -50,6 +56,7 @@ pub struct MigrateMsg {}
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Config {},
+    PendingOwner {},
     State {},
     Member {
         address: String
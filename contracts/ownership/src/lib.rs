@@ -0,0 +1,11 @@
+mod error;
+mod migration;
+mod ownership;
+
+pub use crate::error::OwnershipError;
+pub use crate::migration::{check_migration, MigrationError, PreviousVersion};
+pub use crate::ownership::{
+    accept_ownership, cancel_ownership_proposal, propose_new_owner, query_pending_owner,
+    PendingOwner, PendingOwnerResponse, PENDING_OWNER,
+};
+pub use cw0::Expiration;
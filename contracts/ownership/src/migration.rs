@@ -0,0 +1,85 @@
+use cosmwasm_std::{StdError, Storage};
+use cw2::get_contract_version;
+use semver::Version;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum MigrationError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Cannot migrate from different contract type: {previous_contract}")]
+    CannotMigrate { previous_contract: String },
+
+    #[error("Cannot migrate from newer version {previous_version}")]
+    CannotMigrateVersion { previous_version: String },
+
+    #[error("Semver parsing error: {0}")]
+    SemVer(String),
+}
+
+impl From<semver::Error> for MigrationError {
+    fn from(err: semver::Error) -> Self {
+        Self::SemVer(err.to_string())
+    }
+}
+
+/// Contract name and version stored before the migration
+#[derive(Clone, Debug, PartialEq)]
+pub struct PreviousVersion {
+    pub contract: String,
+    pub version: Version,
+}
+
+/// Checks the stored contract is one of the given contract names and refuses downgrades,
+/// same version only refreshes the stored one
+pub fn check_migration(
+    storage: &dyn Storage,
+    contract_names: &[&str],
+    current_version: &str,
+) -> Result<PreviousVersion, MigrationError> {
+    let stored = get_contract_version(storage)?;
+    if !contract_names.contains(&stored.contract.as_str()) {
+        return Err(MigrationError::CannotMigrate {
+            previous_contract: stored.contract,
+        });
+    }
+
+    let previous: Version = stored.version.parse()?;
+    let current: Version = current_version.parse()?;
+    if previous > current {
+        return Err(MigrationError::CannotMigrateVersion {
+            previous_version: stored.version,
+        });
+    }
+
+    Ok(PreviousVersion {
+        contract: stored.contract,
+        version: previous,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::MockStorage;
+    use cw2::set_contract_version;
+
+    use super::*;
+
+    #[test]
+    fn check_versions() {
+        let mut storage = MockStorage::new();
+
+        set_contract_version(&mut storage, "crates.io:first", "0.1.0").unwrap();
+        let previous = check_migration(&storage, &["crates.io:first"], "0.2.0").unwrap();
+        assert_eq!(previous.version, Version::new(0, 1, 0));
+        assert_eq!(previous.contract, "crates.io:first");
+
+        let err = check_migration(&storage, &["crates.io:second"], "0.2.0").unwrap_err();
+        assert_eq!(err, MigrationError::CannotMigrate { previous_contract: "crates.io:first".into() });
+
+        set_contract_version(&mut storage, "crates.io:first", "0.3.0").unwrap();
+        let err = check_migration(&storage, &["crates.io:first"], "0.2.0").unwrap_err();
+        assert_eq!(err, MigrationError::CannotMigrateVersion { previous_version: "0.3.0".into() });
+    }
+}
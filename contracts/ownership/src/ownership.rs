@@ -0,0 +1,227 @@
+use cosmwasm_std::{Addr, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Storage};
+use cw0::Expiration;
+use cw_storage_plus::Item;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::error::OwnershipError;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PendingOwner {
+    pub owner: Addr,
+    /// The proposal cannot be accepted after it expires
+    pub expiry: Option<Expiration>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PendingOwnerResponse {
+    pub owner: Option<String>,
+    pub expiry: Option<Expiration>,
+}
+
+pub const PENDING_OWNER: Item<PendingOwner> = Item::new("pending_owner");
+
+/// Records the new owner proposed by the current one, replacing any previous proposal
+pub fn propose_new_owner(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: &Addr,
+    new_owner: String,
+    expiry: Option<Expiration>,
+) -> Result<Response, OwnershipError> {
+    // authorized owner
+    if info.sender != *owner {
+        return Err(OwnershipError::Unauthorized {});
+    }
+    if matches!(expiry, Some(e) if e.is_expired(&env.block)) {
+        return Err(OwnershipError::ProposalExpired {});
+    }
+
+    let new_owner = deps.api.addr_validate(&new_owner)?;
+    PENDING_OWNER.save(
+        deps.storage,
+        &PendingOwner {
+            owner: new_owner.clone(),
+            expiry,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "propose_new_owner")
+        .add_attribute("sender", info.sender)
+        .add_attribute("new_owner", new_owner))
+}
+
+pub fn cancel_ownership_proposal(
+    deps: DepsMut,
+    info: MessageInfo,
+    owner: &Addr,
+) -> Result<Response, OwnershipError> {
+    // authorized owner
+    if info.sender != *owner {
+        return Err(OwnershipError::Unauthorized {});
+    }
+    if PENDING_OWNER.may_load(deps.storage)?.is_none() {
+        return Err(OwnershipError::NoPendingOwner {});
+    }
+    PENDING_OWNER.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_ownership_proposal")
+        .add_attribute("sender", info.sender))
+}
+
+/// Clears the proposal accepted by the sender and returns the new owner,
+/// the caller is responsible for storing it in its config
+pub fn accept_ownership(
+    storage: &mut dyn Storage,
+    env: &Env,
+    sender: &Addr,
+) -> Result<Addr, OwnershipError> {
+    let pending = PENDING_OWNER
+        .may_load(storage)?
+        .ok_or(OwnershipError::NoPendingOwner {})?;
+    if *sender != pending.owner {
+        return Err(OwnershipError::Unauthorized {});
+    }
+    if matches!(pending.expiry, Some(e) if e.is_expired(&env.block)) {
+        return Err(OwnershipError::ProposalExpired {});
+    }
+    PENDING_OWNER.remove(storage);
+
+    Ok(pending.owner)
+}
+
+pub fn query_pending_owner(deps: Deps) -> StdResult<PendingOwnerResponse> {
+    let pending = PENDING_OWNER.may_load(deps.storage)?;
+    Ok(PendingOwnerResponse {
+        owner: pending.as_ref().map(|p| p.owner.to_string()),
+        expiry: pending.and_then(|p| p.expiry),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+
+    use super::*;
+
+    const OWNER: &str = "owner";
+    const NEW_OWNER: &str = "new_owner";
+
+    #[test]
+    fn propose_and_accept() {
+        let mut deps = mock_dependencies(&[]);
+        let owner = Addr::unchecked(OWNER);
+        let env = mock_env();
+
+        // only owner can propose
+        let err = propose_new_owner(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(NEW_OWNER, &[]),
+            &owner,
+            NEW_OWNER.into(),
+            None,
+        )
+            .unwrap_err();
+        assert_eq!(err, OwnershipError::Unauthorized {});
+
+        let expiry = Expiration::AtHeight(env.block.height + 10);
+        propose_new_owner(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(OWNER, &[]),
+            &owner,
+            NEW_OWNER.into(),
+            Some(expiry),
+        )
+            .unwrap();
+        assert_eq!(
+            query_pending_owner(deps.as_ref()).unwrap(),
+            PendingOwnerResponse {
+                owner: Some(NEW_OWNER.into()),
+                expiry: Some(expiry),
+            }
+        );
+
+        // only proposed address can accept
+        let err = accept_ownership(deps.as_mut().storage, &env, &owner).unwrap_err();
+        assert_eq!(err, OwnershipError::Unauthorized {});
+
+        let new_owner =
+            accept_ownership(deps.as_mut().storage, &env, &Addr::unchecked(NEW_OWNER)).unwrap();
+        assert_eq!(new_owner, Addr::unchecked(NEW_OWNER));
+        assert_eq!(query_pending_owner(deps.as_ref()).unwrap().owner, None);
+
+        // proposal is consumed
+        let err = accept_ownership(deps.as_mut().storage, &env, &new_owner).unwrap_err();
+        assert_eq!(err, OwnershipError::NoPendingOwner {});
+    }
+
+    #[test]
+    fn expired_proposal() {
+        let mut deps = mock_dependencies(&[]);
+        let owner = Addr::unchecked(OWNER);
+        let mut env = mock_env();
+
+        // cannot propose with past expiry
+        let err = propose_new_owner(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(OWNER, &[]),
+            &owner,
+            NEW_OWNER.into(),
+            Some(Expiration::AtHeight(env.block.height)),
+        )
+            .unwrap_err();
+        assert_eq!(err, OwnershipError::ProposalExpired {});
+
+        propose_new_owner(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(OWNER, &[]),
+            &owner,
+            NEW_OWNER.into(),
+            Some(Expiration::AtHeight(env.block.height + 10)),
+        )
+            .unwrap();
+
+        env.block.height += 10;
+        let err = accept_ownership(deps.as_mut().storage, &env, &Addr::unchecked(NEW_OWNER))
+            .unwrap_err();
+        assert_eq!(err, OwnershipError::ProposalExpired {});
+    }
+
+    #[test]
+    fn cancel_proposal() {
+        let mut deps = mock_dependencies(&[]);
+        let owner = Addr::unchecked(OWNER);
+
+        let err = cancel_ownership_proposal(deps.as_mut(), mock_info(OWNER, &[]), &owner)
+            .unwrap_err();
+        assert_eq!(err, OwnershipError::NoPendingOwner {});
+
+        propose_new_owner(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[]),
+            &owner,
+            NEW_OWNER.into(),
+            None,
+        )
+            .unwrap();
+
+        // only owner can cancel
+        let err = cancel_ownership_proposal(deps.as_mut(), mock_info(NEW_OWNER, &[]), &owner)
+            .unwrap_err();
+        assert_eq!(err, OwnershipError::Unauthorized {});
+
+        cancel_ownership_proposal(deps.as_mut(), mock_info(OWNER, &[]), &owner).unwrap();
+        let err =
+            accept_ownership(deps.as_mut().storage, &mock_env(), &Addr::unchecked(NEW_OWNER))
+                .unwrap_err();
+        assert_eq!(err, OwnershipError::NoPendingOwner {});
+    }
+}
@@ -2,12 +2,16 @@ use std::env::current_dir;
 use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
+use cw4::{HooksResponse, MemberListResponse, MemberResponse, TotalWeightResponse};
+use ownership::PendingOwnerResponse;
 use staking::state::Config as ConfigResponse;
 use staking::state::State as StateResponse;
 
 pub use staking::msg::{
-    ExecuteMsg, InstantiateMsg, QueryMsg, MigrateMsg, ReceiveMsg, MemberResponse,
-    MemberListResponse,
+    ExecuteMsg, InstantiateMsg, QueryMsg, MigrateMsg, ReceiveMsg, StakerResponse,
+    StakerListResponse, AprResponse, SimulateRewardResponse, DistributionStatusResponse,
+    RewardReserveResponse, StakeAtHeightResponse, TotalStakeAtHeightResponse,
+    SimulateInstantClaimResponse,
 };
 
 fn main() {
@@ -24,6 +28,18 @@ fn main() {
 
     export_schema(&schema_for!(ConfigResponse), &out_dir);
     export_schema(&schema_for!(StateResponse), &out_dir);
-    export_schema(&schema_for!(MemberListResponse), &out_dir);
+    export_schema(&schema_for!(PendingOwnerResponse), &out_dir);
+    export_schema(&schema_for!(StakerListResponse), &out_dir);
+    export_schema(&schema_for!(StakerResponse), &out_dir);
+    export_schema(&schema_for!(AprResponse), &out_dir);
+    export_schema(&schema_for!(SimulateRewardResponse), &out_dir);
+    export_schema(&schema_for!(DistributionStatusResponse), &out_dir);
+    export_schema(&schema_for!(RewardReserveResponse), &out_dir);
     export_schema(&schema_for!(MemberResponse), &out_dir);
+    export_schema(&schema_for!(MemberListResponse), &out_dir);
+    export_schema(&schema_for!(TotalWeightResponse), &out_dir);
+    export_schema(&schema_for!(HooksResponse), &out_dir);
+    export_schema(&schema_for!(StakeAtHeightResponse), &out_dir);
+    export_schema(&schema_for!(TotalStakeAtHeightResponse), &out_dir);
+    export_schema(&schema_for!(SimulateInstantClaimResponse), &out_dir);
 }
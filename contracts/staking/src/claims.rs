@@ -0,0 +1,133 @@
+use cosmwasm_std::{Addr, BlockInfo, Deps, Order, StdError, StdResult, Storage, Uint128};
+use cw0::Expiration;
+pub use cw_controllers::{Claim, ClaimsResponse};
+use cw_storage_plus::Map;
+
+/// Unbonding claims, stored the same way as `cw_controllers::Claims` but giving
+/// access to the whole queue of the address
+pub struct Claims<'a>(Map<'a, &'a Addr, Vec<Claim>>);
+
+impl<'a> Claims<'a> {
+    pub const fn new(storage_key: &'a str) -> Self {
+        Claims(Map::new(storage_key))
+    }
+
+    /// Creates a claim, such that the given address can claim an amount of tokens after
+    /// the release date
+    pub fn create_claim(
+        &self,
+        storage: &mut dyn Storage,
+        addr: &Addr,
+        amount: Uint128,
+        release_at: Expiration,
+    ) -> StdResult<()> {
+        self.0.update(storage, addr, |old| -> StdResult<_> {
+            let mut claims = old.unwrap_or_default();
+            claims.push(Claim { amount, release_at });
+            Ok(claims)
+        })?;
+        Ok(())
+    }
+
+    /// Removes mature claims of the address, oldest first, up to an optional amount.
+    /// The claim exceeding the amount is split and its rest keeps waiting.
+    /// Returns the released claims
+    pub fn claim_tokens(
+        &self,
+        storage: &mut dyn Storage,
+        addr: &Addr,
+        block: &BlockInfo,
+        amount: Option<Uint128>,
+    ) -> StdResult<Vec<Claim>> {
+        let mut released = vec![];
+        self.0.update(storage, addr, |claims| -> StdResult<_> {
+            let (claimed, waiting) = split_claims(claims.unwrap_or_default(), block, amount);
+            released = claimed;
+            Ok(waiting)
+        })?;
+        Ok(released)
+    }
+
+    /// Returns the claims `claim_tokens` would release, without removing them
+    pub fn releasable_claims(
+        &self,
+        deps: Deps,
+        addr: &Addr,
+        block: &BlockInfo,
+        amount: Option<Uint128>,
+    ) -> StdResult<Vec<Claim>> {
+        let claims = self.0.may_load(deps.storage, addr)?.unwrap_or_default();
+        Ok(split_claims(claims, block, amount).0)
+    }
+
+    /// Removes the given amount from the claims of the address, newest claims first.
+    /// The amount cannot exceed the sum of the claims
+    pub fn rebond(&self, storage: &mut dyn Storage, addr: &Addr, mut amount: Uint128) -> StdResult<()> {
+        self.0.update(storage, addr, |claims| -> StdResult<_> {
+            let mut claims = claims.unwrap_or_default();
+            while !amount.is_zero() {
+                let claim = match claims.last_mut() {
+                    Some(claim) => claim,
+                    None => return Err(StdError::generic_err("Not enough claims to rebond")),
+                };
+                let taken = std::cmp::min(claim.amount, amount);
+                claim.amount -= taken;
+                amount -= taken;
+                if claim.amount.is_zero() {
+                    claims.pop();
+                }
+            }
+            Ok(claims)
+        })?;
+        Ok(())
+    }
+
+    /// Returns true if any address still has claims to be released
+    pub fn any_pending(&self, storage: &dyn Storage) -> StdResult<bool> {
+        for item in self.0.range(storage, None, None, Order::Ascending) {
+            let (_, claims) = item?;
+            if !claims.is_empty() {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Returns the sum of all claims waiting to be released
+    pub fn total_pending(&self, storage: &dyn Storage) -> StdResult<Uint128> {
+        let mut total = Uint128::zero();
+        for item in self.0.range(storage, None, None, Order::Ascending) {
+            let (_, claims) = item?;
+            total += claims.iter().map(|claim| claim.amount).sum::<Uint128>();
+        }
+        Ok(total)
+    }
+
+    pub fn query_claims(&self, deps: Deps, address: &Addr) -> StdResult<ClaimsResponse> {
+        let claims = self.0.may_load(deps.storage, address)?.unwrap_or_default();
+        Ok(ClaimsResponse { claims })
+    }
+}
+
+/// Splits claims into the released and waiting ones, mature claims are released oldest first
+/// up to an optional amount
+fn split_claims(claims: Vec<Claim>, block: &BlockInfo, amount: Option<Uint128>) -> (Vec<Claim>, Vec<Claim>) {
+    let mut remaining = amount;
+    let mut released = vec![];
+    let mut waiting = vec![];
+    for mut claim in claims {
+        if !claim.release_at.is_expired(block) || remaining == Some(Uint128::zero()) {
+            waiting.push(claim);
+            continue;
+        }
+        if let Some(left) = remaining.as_mut() {
+            if claim.amount > *left {
+                waiting.push(Claim { amount: claim.amount - *left, release_at: claim.release_at });
+                claim.amount = *left;
+            }
+            *left -= claim.amount;
+        }
+        released.push(claim);
+    }
+    (released, waiting)
+}
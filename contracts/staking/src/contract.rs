@@ -1,17 +1,22 @@
 use std::cmp;
+use std::convert::{TryFrom, TryInto};
 use std::ops::{Div, Mul};
 
-use cosmwasm_std::{Addr, BankMsg, Binary, Coin, Decimal, Deps, DepsMut, Env, from_slice, MessageInfo, Order, Response, StdError, StdResult, SubMsg, to_binary, Uint128, WasmMsg, WasmQuery};
+use cosmwasm_std::{Addr, Api, BankMsg, Binary, BlockInfo, Coin, Decimal, Deps, DepsMut, Empty, Env, from_slice, MessageInfo, Order, Response, StdError, StdResult, Storage, SubMsg, to_binary, Uint128, WasmMsg, WasmQuery};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cw0::{Duration, maybe_addr, must_pay};
-use cw20::{Balance, BalanceResponse, Cw20CoinVerified, Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg};
-use cw2::{get_contract_version, set_contract_version};
-use cw_storage_plus::Bound;
-
+use cw0::{Duration, Expiration, maybe_addr, must_pay, PaymentError};
+use cw20::{Balance, BalanceResponse, Cw20CoinVerified, Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg, Denom};
+use cw2::set_contract_version;
+use cw4::{Member, MemberChangedHookMsg, MemberDiff, MemberListResponse, MemberResponse, TotalWeightResponse};
+use cw_storage_plus::{Bound, U64Key};
+use ownership::{accept_ownership, cancel_ownership_proposal, check_migration, propose_new_owner, query_pending_owner};
+
+use crate::claims::Claim;
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, MemberListResponse, MemberListResponseItem, MemberResponse, MemberResponseItem, MigrateMsg, NewConfig, QueryMsg, ReceiveMsg};
-use crate::state::{CLAIMS, Config, CONFIG, MemberInfo, MEMBERS, State, STATE};
+use crate::migrations::migrate_from;
+use crate::msg::{AprResponse, DistributionStatusResponse, ExecuteMsg, InstantiateMsg, StakerListResponse, StakerListResponseItem, StakerResponse, StakerResponseItem, MemberRewardResponse, MigrateMsg, NewConfig, QueryMsg, ReceiveMsg, RewardReserveResponse, ScheduleStatus, SimulateInstantClaimResponse, SimulateRewardResponse, StakeAtHeightResponse, StreamAprResponse, StreamDistributionStatus, StreamReserveResponse, TotalStakeAtHeightResponse};
+use crate::state::{CLAIMS, Config, CONFIG, EarlyUnlock, HOOKS, InstantClaimCurve, Lock, LOCK_EXPIRIES, LOCK_HOLDERS, LockTier, MemberInfo, MemberReward, MEMBERS, PenaltySplit, RewardStream, Schedule, STAKE_SNAPSHOTS, State, STATE, TOTAL_STAKE_KEY, TOTAL_STAKE_SNAPSHOTS, UNLOCK_INDICES};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:fcq-staking";
@@ -23,29 +28,50 @@ const YEAR_IN_SEC: u64 = 365*24*3600;
 pub fn instantiate(
     deps: DepsMut,
     _env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
     let config = Config {
         owner: deps.api.addr_validate(&msg.owner)?,
-        staking_token: deps.api.addr_validate(&msg.staking_token)?,
+        staking_token: validate_denom(deps.api, msg.staking_token)?,
         terraland_token: deps.api.addr_validate(&msg.terraland_token)?,
         unbonding_period: msg.unbonding_period,
         burn_address: deps.api.addr_validate(&msg.burn_address)?,
         instant_claim_percentage_loss: msg.instant_claim_percentage_loss,
-        distribution_schedule: msg.distribution_schedule,
+        reward_streams: validate_reward_streams(deps.api, msg.reward_streams)?,
         fee_config: msg.fee_config,
+        lock_tiers: validate_lock_tiers(msg.lock_tiers)?,
+        early_unlock: validate_early_unlock(msg.early_unlock)?,
+        treasury_address: msg.treasury_address.map(|addr| deps.api.addr_validate(&addr)).transpose()?,
+        instant_claim_split: msg.instant_claim_split,
+        instant_claim_curve: msg.instant_claim_curve,
     };
+    let pays_staking_token = compound_stream(&config).is_some();
+    validate_penalty_split(&config.instant_claim_split, &config.treasury_address, pays_staking_token)?;
 
-    let state = State {
+    let mut state = State {
         total_stake: Default::default(),
         last_updated: Default::default(),
-        global_reward_index: Default::default(),
+        global_reward_indices: Default::default(),
         num_of_members: Default::default(),
+        reward_reserves: Default::default(),
+        undistributed: Default::default(),
     };
 
+    // cw20 tokens cannot be sent along, their streams are funded with FundRewards first
+    // and scheduled with AppendSchedule afterwards
+    for (i, stream) in config.reward_streams.iter().enumerate() {
+        if matches!(stream.token, Denom::Cw20(_)) && !stream.distribution_schedule.is_empty() {
+            return Err(ContractError::Cw20ScheduleOnInstantiate(i as u32));
+        }
+    }
+
+    // native funds sent along fund the initial schedules
+    credit_reward_funds(&config, &mut state, reward_funds(Balance::from(info.funds)))?;
+    validate_funding(&config.reward_streams, &state)?;
+
     CONFIG.save(deps.storage, &config)?;
     STATE.save(deps.storage, &state)?;
 
@@ -53,14 +79,15 @@ pub fn instantiate(
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
-    let version = get_contract_version(deps.storage)?;
-    if version.contract != CONTRACT_NAME {
-        return Err(ContractError::CannotMigrate {
-            previous_contract: version.contract,
-        });
-    }
-    Ok(Response::default())
+pub fn migrate(mut deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let previous = check_migration(deps.storage, &[CONTRACT_NAME], CONTRACT_VERSION)?;
+    migrate_from(deps.branch(), &env, &previous.version)?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("previous_version", previous.version.to_string())
+        .add_attribute("new_version", CONTRACT_VERSION))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -72,11 +99,36 @@ pub fn execute(
 ) -> Result<Response, ContractError> {
     match msg {
         ExecuteMsg::UpdateConfig(new_config) => execute_update_config(deps, env, info, new_config),
+        ExecuteMsg::AppendSchedule { stream, schedule } =>
+            execute_append_schedule(deps, env, info, stream, schedule),
+        ExecuteMsg::ProposeNewOwner { owner, expiry } => {
+            let cfg = CONFIG.load(deps.storage)?;
+            Ok(propose_new_owner(deps, env, info, &cfg.owner, owner, expiry)?)
+        }
+        ExecuteMsg::AcceptOwnership {} => execute_accept_ownership(deps, env, info),
+        ExecuteMsg::CancelOwnershipProposal {} => {
+            let cfg = CONFIG.load(deps.storage)?;
+            Ok(cancel_ownership_proposal(deps, info, &cfg.owner)?)
+        }
         ExecuteMsg::Receive(msg) => execute_receive(deps, env, info, msg),
+        ExecuteMsg::Bond { lock } => {
+            let balance = Balance::from(info.funds);
+            execute_bond(deps, env, balance, info.sender.clone(), info.sender, lock)
+        }
+        ExecuteMsg::FundRewards {} => {
+            let balance = Balance::from(info.funds);
+            execute_fund_rewards(deps, balance, info.sender)
+        }
+        ExecuteMsg::AddHook { addr } => execute_add_hook(deps, info, addr),
+        ExecuteMsg::RemoveHook { addr } => execute_remove_hook(deps, info, addr),
         ExecuteMsg::Unbond { tokens: amount } => execute_unbond(deps, env, info, amount),
-        ExecuteMsg::Claim {} => execute_claim(deps, env, info),
-        ExecuteMsg::InstantClaim {} => execute_instant_claim(deps, env, info),
+        ExecuteMsg::Rebond { amount } => execute_rebond(deps, env, info, amount),
+        ExecuteMsg::Claim { amount } => execute_claim(deps, env, info, amount),
+        ExecuteMsg::InstantClaim { amount } => execute_instant_claim(deps, env, info, amount),
         ExecuteMsg::Withdraw {} => execute_withdraw(deps, env, info),
+        ExecuteMsg::Compound {} => execute_compound(deps, env, info),
+        ExecuteMsg::SetAutoCompound { enabled } =>
+            execute_set_auto_compound(deps, info, enabled),
         ExecuteMsg::UstWithdraw { recipient, amount } =>
             execute_ust_withdraw(deps, env, info, recipient, amount),
         ExecuteMsg::TokenWithdraw { token, recipient } =>
@@ -86,48 +138,158 @@ pub fn execute(
 
 pub fn execute_update_config(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     new_config: NewConfig,
 ) -> Result<Response, ContractError> {
     // authorized owner
-    let cfg = CONFIG.load(deps.storage)?;
+    let mut cfg = CONFIG.load(deps.storage)?;
     if info.sender != cfg.owner {
         return Err(ContractError::Unauthorized {});
     }
 
     let api = deps.api;
 
-    CONFIG.update(deps.storage, |mut exists| -> StdResult<_> {
-        if let Some(addr) = new_config.owner {
-            exists.owner = api.addr_validate(&addr)?;
-        }
-        if let Some(addr) = new_config.staking_token {
-            exists.staking_token = api.addr_validate(&addr)?;
-        }
-        if let Some(addr) = new_config.burn_address {
-            exists.burn_address = api.addr_validate(&addr)?;
-        }
-        if let Some(period) = new_config.unbonding_period {
-            exists.unbonding_period = period;
-        }
-        if let Some(percentage) = new_config.instant_claim_percentage_loss {
-            exists.instant_claim_percentage_loss = percentage;
-        }
-        if let Some(schedule) = new_config.distribution_schedule {
-            exists.distribution_schedule = schedule;
-        }
-        if let Some(fee_config) = new_config.fee_config {
-            exists.fee_config = fee_config;
+    let reward_streams = match new_config.reward_streams {
+        Some(streams) => {
+            let streams = validate_reward_streams(api, streams)?;
+            // existing streams keep their position and token, so indices stay aligned
+            if streams.len() < cfg.reward_streams.len()
+                || cfg.reward_streams.iter().zip(streams.iter()).any(|(a, b)| a.token != b.token) {
+                return Err(ContractError::InvalidRewardStreams {});
+            }
+            for (i, stream) in streams.iter().enumerate() {
+                let current = cfg.reward_streams.get(i)
+                    .map(|stream| stream.distribution_schedule.as_slice())
+                    .unwrap_or_default();
+                validate_schedule_update(current, &stream.distribution_schedule, env.block.time.seconds())?;
+            }
+            Some(streams)
         }
-        Ok(exists)
-    })?;
+        None => None,
+    };
+    let lock_tiers = new_config.lock_tiers.map(validate_lock_tiers).transpose()?;
+    let early_unlock = new_config.early_unlock.map(validate_early_unlock).transpose()?;
+    let treasury_address = match new_config.treasury_address {
+        Some(addr) => Some(api.addr_validate(&addr)?),
+        None => cfg.treasury_address.clone(),
+    };
+    let staking_token = new_config.staking_token
+        .map(|token| validate_denom(api, token))
+        .transpose()?;
+
+    // staked and claimed tokens are paid back in the staking token
+    if matches!(&staking_token, Some(token) if *token != cfg.staking_token)
+        && (!STATE.load(deps.storage)?.total_stake.is_zero() || CLAIMS.any_pending(deps.storage)?)
+    {
+        return Err(ContractError::StakingTokenInUse {});
+    }
+    let instant_claim_split = new_config.instant_claim_split
+        .unwrap_or_else(|| cfg.instant_claim_split.clone());
+    let pays_staking_token = reward_streams.as_ref()
+        .unwrap_or(&cfg.reward_streams)
+        .iter()
+        .any(|stream| stream.token == *staking_token.as_ref().unwrap_or(&cfg.staking_token));
+    validate_penalty_split(&instant_claim_split, &treasury_address, pays_staking_token)?;
+
+    if let Some(streams) = &reward_streams {
+        validate_funding(streams, &STATE.load(deps.storage)?)?;
+    }
+
+    // rewards up to now are computed with the schedules they were distributed by
+    let diffs = match reward_streams {
+        Some(_) => checkpoint_reward_indices(deps.storage, &env, &cfg)?,
+        None => vec![],
+    };
+
+    if let Some(token) = staking_token {
+        cfg.staking_token = token;
+    }
+    if let Some(addr) = new_config.burn_address {
+        cfg.burn_address = api.addr_validate(&addr)?;
+    }
+    if let Some(period) = new_config.unbonding_period {
+        cfg.unbonding_period = period;
+    }
+    if let Some(percentage) = new_config.instant_claim_percentage_loss {
+        cfg.instant_claim_percentage_loss = percentage;
+    }
+    if let Some(streams) = reward_streams {
+        cfg.reward_streams = streams;
+    }
+    if let Some(fee_config) = new_config.fee_config {
+        cfg.fee_config = fee_config;
+    }
+    if let Some(lock_tiers) = lock_tiers {
+        cfg.lock_tiers = lock_tiers;
+    }
+    if let Some(early_unlock) = early_unlock {
+        cfg.early_unlock = early_unlock;
+    }
+    cfg.treasury_address = treasury_address;
+    cfg.instant_claim_split = instant_claim_split;
+    if let Some(curve) = new_config.instant_claim_curve {
+        cfg.instant_claim_curve = curve;
+    }
+    CONFIG.save(deps.storage, &cfg)?;
 
     Ok(Response::new()
+        .add_submessages(member_changed_hooks(deps.storage, diffs)?)
         .add_attribute("action", "update_config")
         .add_attribute("sender", info.sender))
 }
 
+pub fn execute_append_schedule(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    stream: u32,
+    schedule: Schedule,
+) -> Result<Response, ContractError> {
+    // authorized owner
+    let mut cfg = CONFIG.load(deps.storage)?;
+    if info.sender != cfg.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let time = env.block.time.seconds();
+    let current = &cfg.reward_streams.get(stream as usize)
+        .ok_or(ContractError::RewardStreamNotFound(stream))?
+        .distribution_schedule;
+    let mut schedules = current.clone();
+    schedules.push(schedule);
+    validate_schedules(&schedules)?;
+    validate_schedule_update(current, &schedules, time)?;
+
+    cfg.reward_streams[stream as usize].distribution_schedule = schedules;
+    validate_funding(&cfg.reward_streams, &STATE.load(deps.storage)?)?;
+
+    let diffs = checkpoint_reward_indices(deps.storage, &env, &cfg)?;
+    CONFIG.save(deps.storage, &cfg)?;
+
+    Ok(Response::new()
+        .add_submessages(member_changed_hooks(deps.storage, diffs)?)
+        .add_attribute("action", "append_schedule")
+        .add_attribute("stream", stream.to_string())
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_accept_ownership(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let new_owner = accept_ownership(deps.storage, &env, &info.sender)?;
+    CONFIG.update(deps.storage, |mut exists| -> StdResult<_> {
+        exists.owner = new_owner;
+        Ok(exists)
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "accept_ownership")
+        .add_attribute("owner", info.sender))
+}
+
 pub fn execute_receive(
     deps: DepsMut,
     env: Env,
@@ -145,8 +307,16 @@ pub fn execute_receive(
     });
     let api = deps.api;
     match msg {
-        ReceiveMsg::Bond {} => {
-            execute_bond(deps, env, balance, api.addr_validate(&wrapper.sender)?)
+        ReceiveMsg::Bond { lock } => {
+            let sender = api.addr_validate(&wrapper.sender)?;
+            execute_bond(deps, env, balance, sender.clone(), sender, lock)
+        }
+        ReceiveMsg::BondFor { beneficiary } => {
+            let sender = api.addr_validate(&wrapper.sender)?;
+            execute_bond(deps, env, balance, sender, api.addr_validate(&beneficiary)?, None)
+        }
+        ReceiveMsg::FundRewards {} => {
+            execute_fund_rewards(deps, balance, api.addr_validate(&wrapper.sender)?)
         }
     }
 }
@@ -156,61 +326,305 @@ pub fn execute_bond(
     env: Env,
     amount: Balance,
     sender: Addr,
+    beneficiary: Addr,
+    lock: Option<u64>,
 ) -> Result<Response, ContractError> {
     let cfg = CONFIG.load(deps.storage)?;
 
     // ensure the sent token was proper
-    let amount = match &amount {
-        Balance::Cw20(token) => {
-            if token.address == cfg.staking_token {
+    let amount = match (&cfg.staking_token, &amount) {
+        (Denom::Cw20(staking_token), Balance::Cw20(token)) => {
+            if token.address == *staking_token {
                 Ok(token.amount)
             } else {
                 Err(ContractError::InvalidToken(token.address.to_string()))
             }
         }
+        (Denom::Native(denom), Balance::Native(balance)) => match balance.0.as_slice() {
+            [coin] if coin.denom == *denom => Ok(coin.amount),
+            [] => Err(PaymentError::NoFunds {}.into()),
+            [_] => Err(PaymentError::MissingDenom(denom.clone()).into()),
+            _ => Err(PaymentError::MultipleDenoms {}.into()),
+        },
         _ => Err(ContractError::MissedToken {})
     }?;
 
-    let mut state = STATE.load(deps.storage)?;
-    let mut member_info = MEMBERS.may_load(deps.storage, &sender)?
-        .unwrap_or(Default::default());
+    // locked tokens get the multiplier of the chosen tier
+    let tier = lock
+        .map(|duration| {
+            cfg.lock_tiers.iter()
+                .find(|tier| tier.duration == duration)
+                .ok_or(ContractError::LockTierNotFound(duration))
+        })
+        .transpose()?;
+
+    let diffs = stake_tokens(deps.storage, &env, &cfg, &beneficiary, amount, tier)?;
+    let messages = member_changed_hooks(deps.storage, diffs)?;
 
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "bond")
+        .add_attribute("amount", amount)
+        .add_attribute("lock", lock.unwrap_or_default().to_string())
+        .add_attribute("sender", sender)
+        .add_attribute("beneficiary", beneficiary))
+}
+
+/// Adds tokens to the member stake, optionally locked for the given tier,
+/// and returns the changes of member weights
+fn stake_tokens(
+    storage: &mut dyn Storage,
+    env: &Env,
+    cfg: &Config,
+    sender: &Addr,
+    amount: Uint128,
+    tier: Option<&LockTier>,
+) -> StdResult<Vec<MemberDiff>> {
     // compute reward and updates member info with new rewards
-    update_member_reward(&state, &cfg, env.block.time.seconds(), &mut member_info)?;
+    let mut state = STATE.load(storage)?;
+    let mut diffs = update_state(storage, env, cfg, &mut state)?;
+    let existing = MEMBERS.may_load(storage, sender)?;
+    let is_new_member = existing.is_none();
+    let mut member_info = existing.unwrap_or_default();
+    let time = env.block.time.seconds();
+    let old_weight = member_info.weight();
+    settle_member_reward(storage, &[], &state.global_reward_indices, &mut member_info, time)?;
+    let weight = member_info.weight();
+    auto_compound_member_reward(cfg, &mut state, &mut member_info);
 
     // update member stake
     member_info.stake += amount;
+    if let Some(tier) = tier {
+        let lock = Lock {
+            amount,
+            multiplier: tier.multiplier,
+            unlock_at: time + tier.duration,
+        };
+        LOCK_EXPIRIES.update(storage, U64Key::new(lock.unlock_at), |boost| -> StdResult<_> {
+            Ok(boost.unwrap_or_default() + lock.boost())
+        })?;
+        LOCK_HOLDERS.save(storage, (U64Key::new(lock.unlock_at), sender), &Empty {})?;
+        member_info.locks.push(lock);
+    }
 
-    // update state with new stake and global_reward_index
-    state.total_stake += amount;
-    state.last_updated = env.block.time.seconds();
-    state.global_reward_index = member_info.reward_index;
-    if !MEMBERS.has(deps.storage, &sender) {
+    // update state with new stake, fees kept while there was no stake go to the new stakers
+    state.total_stake = state.total_stake - weight + member_info.weight();
+    distribute_to_stakers(cfg, &mut state);
+    if is_new_member {
         state.num_of_members += 1;
     }
 
     // save new member info and state in storage
-    MEMBERS.save(deps.storage, &sender, &member_info)?;
+    MEMBERS.save(storage, sender, &member_info)?;
+    STATE.save(storage, &state)?;
+    snapshot_stake(storage, env, sender, member_info.weight(), state.total_stake)?;
+
+    let old_weight = if is_new_member { None } else { Some(old_weight) };
+    diffs.push(member_diff(sender, old_weight, member_info.weight()));
+    Ok(diffs)
+}
+
+/// Records member weight and total stake for queries at the current block height
+fn snapshot_stake(
+    storage: &mut dyn Storage,
+    env: &Env,
+    addr: &Addr,
+    weight: Uint128,
+    total_stake: Uint128,
+) -> StdResult<()> {
+    let height = env.block.height;
+    STAKE_SNAPSHOTS.save(storage, addr, &weight, height)?;
+    TOTAL_STAKE_SNAPSHOTS.save(storage, TOTAL_STAKE_KEY, &total_stake, height)
+}
+
+/// Builds cw4 member diff, weights are reported as u64
+fn member_diff(addr: &Addr, old: Option<Uint128>, new: Uint128) -> MemberDiff {
+    MemberDiff::new(addr, old.map(weight_to_u64), Some(weight_to_u64(new)))
+}
+
+/// Weight reported to cw4 clients, saturated at `u64::MAX` so it never blocks stake changes
+fn weight_to_u64(weight: Uint128) -> u64 {
+    u64::try_from(weight.u128()).unwrap_or(u64::MAX)
+}
+
+/// Prepares `MemberChangedHookMsg` for every registered hook
+fn member_changed_hooks(storage: &dyn Storage, diffs: Vec<MemberDiff>) -> StdResult<Vec<SubMsg>> {
+    if diffs.is_empty() {
+        return Ok(vec![]);
+    }
+    let msg = MemberChangedHookMsg::new(diffs);
+    HOOKS.prepare_hooks(storage, |hook| msg.clone().into_cosmos_msg(hook).map(SubMsg::new))
+}
+
+pub fn execute_fund_rewards(
+    deps: DepsMut,
+    balance: Balance,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+
+    let funds = reward_funds(balance);
+    if funds.iter().all(|(_, amount)| amount.is_zero()) {
+        return Err(ContractError::Payment(PaymentError::NoFunds {}));
+    }
+
+    let mut state = STATE.load(deps.storage)?;
+    let tokens = credit_reward_funds(&cfg, &mut state, funds)?;
     STATE.save(deps.storage, &state)?;
 
     Ok(Response::new()
-        .add_attribute("action", "bond")
-        .add_attribute("amount", amount)
+        .add_attribute("action", "fund_rewards")
+        .add_attribute("tokens", tokens.join(", "))
         .add_attribute("sender", sender))
 }
 
-fn update_member_reward(state: &State, cfg: &Config, time: u64, member_info: &mut MemberInfo) -> StdResult<()> {
-    let global_reward_index = compute_reward_index(&cfg, &state, time)?;
+fn reward_funds(balance: Balance) -> Vec<(Denom, Uint128)> {
+    match balance {
+        Balance::Cw20(token) => vec![(Denom::Cw20(token.address), token.amount)],
+        Balance::Native(balance) => balance.0.into_iter()
+            .map(|coin| (Denom::Native(coin.denom), coin.amount))
+            .collect(),
+    }
+}
+
+/// Deposit is credited to the first stream paying in the token, returns the credited tokens
+fn credit_reward_funds(
+    cfg: &Config,
+    state: &mut State,
+    funds: Vec<(Denom, Uint128)>,
+) -> Result<Vec<String>, ContractError> {
+    let mut tokens = vec![];
+    for (token, amount) in funds {
+        let stream = cfg.reward_streams.iter()
+            .position(|stream| stream.token == token)
+            .ok_or_else(|| ContractError::UnknownRewardToken(denom_to_str(&token).to_string()))?;
+        state.reward_reserve_mut(stream).funded += amount;
+        tokens.push(coin_to_string(amount, denom_to_str(&token)));
+    }
+    Ok(tokens)
+}
 
-    let reward = compute_member_reward(&member_info, global_reward_index);
+fn update_member_reward(global_reward_indices: &[Decimal], member_info: &mut MemberInfo) {
+    // streams added after the member joined start from zero index
+    member_info.rewards.resize(global_reward_indices.len(), MemberReward::default());
 
-    member_info.reward_index = global_reward_index;
-    member_info.pending_reward = reward;
+    let weight = member_info.weight();
+    for (reward, global_reward_index) in member_info.rewards.iter_mut().zip(global_reward_indices) {
+        reward.pending_reward = compute_member_reward(weight, reward, *global_reward_index);
+        reward.reward_index = *global_reward_index;
+    }
+}
 
+/// Updates member rewards up to the global reward indices. Locks which ended meanwhile
+/// boost the weight only until their unlock time and are removed
+fn settle_member_reward(
+    storage: &dyn Storage,
+    unlocked: &[(u64, Vec<Decimal>)],
+    global_reward_indices: &[Decimal],
+    member_info: &mut MemberInfo,
+    time: u64,
+) -> StdResult<()> {
+    member_info.locks.sort_by_key(|lock| lock.unlock_at);
+    while let Some(unlock_at) = member_info.locks.first()
+        .map(|lock| lock.unlock_at)
+        .filter(|unlock_at| *unlock_at <= time)
+    {
+        let indices = match unlocked.iter().find(|(time, _)| *time == unlock_at) {
+            Some((_, indices)) => indices.clone(),
+            None => UNLOCK_INDICES.load(storage, U64Key::new(unlock_at))?,
+        };
+        update_member_reward(&indices, member_info);
+        member_info.locks.retain(|lock| lock.unlock_at != unlock_at);
+    }
+    update_member_reward(global_reward_indices, member_info);
     Ok(())
 }
 
-fn compute_reward_index(cfg: &Config, state: &State, time: u64) -> StdResult<Decimal> {
+/// Brings global reward indices up to the given time. Lock boosts ending meanwhile are
+/// removed from the total stake exactly at their unlock time, returns the indices at those times
+fn advance_state(
+    storage: &dyn Storage,
+    cfg: &Config,
+    state: &mut State,
+    time: u64,
+) -> StdResult<Vec<(u64, Vec<Decimal>)>> {
+    let expiries = LOCK_EXPIRIES
+        .range(storage, None, Some(Bound::inclusive(time.to_be_bytes().to_vec())), Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut unlocked = vec![];
+    for (key, boost) in expiries {
+        let unlock_at = u64::from_be_bytes(key.as_slice().try_into()
+            .map_err(|_| StdError::generic_err("Invalid unlock time key"))?);
+        if unlock_at > state.last_updated {
+            state.global_reward_indices = compute_reward_indices(cfg, state, unlock_at)?;
+            state.last_updated = unlock_at;
+        }
+        state.total_stake = state.total_stake.checked_sub(boost).unwrap_or_default();
+        unlocked.push((unlock_at, state.global_reward_indices.clone()));
+    }
+
+    state.global_reward_indices = compute_reward_indices(cfg, state, time)?;
+    state.last_updated = time;
+    Ok(unlocked)
+}
+
+/// Advances state like `advance_state` and stores the indices at processed unlock times.
+/// Members holding the ended locks lose their boost, returns the changes of their weights
+fn update_state(
+    storage: &mut dyn Storage,
+    env: &Env,
+    cfg: &Config,
+    state: &mut State,
+) -> StdResult<Vec<MemberDiff>> {
+    let unlocked = advance_state(storage, cfg, state, env.block.time.seconds())?;
+    let mut diffs = vec![];
+    for (unlock_at, indices) in &unlocked {
+        LOCK_EXPIRIES.remove(storage, U64Key::new(*unlock_at));
+        UNLOCK_INDICES.save(storage, U64Key::new(*unlock_at), indices)?;
+
+        let holders = LOCK_HOLDERS.prefix(U64Key::new(*unlock_at))
+            .keys(storage, None, None, Order::Ascending)
+            .map(|key| Ok(Addr::unchecked(String::from_utf8(key)?)))
+            .collect::<StdResult<Vec<_>>>()?;
+        for addr in holders {
+            LOCK_HOLDERS.remove(storage, (U64Key::new(*unlock_at), &addr));
+            let mut member_info = match MEMBERS.may_load(storage, &addr)? {
+                Some(member_info) => member_info,
+                None => continue,
+            };
+            let old_weight = member_info.weight();
+            settle_member_reward(storage, &unlocked, indices, &mut member_info, *unlock_at)?;
+            if member_info.weight() != old_weight {
+                MEMBERS.save(storage, &addr, &member_info)?;
+                STAKE_SNAPSHOTS.save(storage, &addr, &member_info.weight(), env.block.height)?;
+                diffs.push(member_diff(&addr, Some(old_weight), member_info.weight()));
+            }
+        }
+    }
+    if !unlocked.is_empty() {
+        TOTAL_STAKE_SNAPSHOTS.save(storage, TOTAL_STAKE_KEY, &state.total_stake, env.block.height)?;
+    }
+    Ok(diffs)
+}
+
+fn compute_reward_indices(cfg: &Config, state: &State, time: u64) -> StdResult<Vec<Decimal>> {
+    cfg.reward_streams.iter().enumerate()
+        .map(|(i, stream)| {
+            let global_reward_index = state.global_reward_indices.get(i)
+                .cloned()
+                .unwrap_or_default();
+            compute_reward_index(&stream.distribution_schedule, state, global_reward_index, time)
+        })
+        .collect()
+}
+
+fn compute_reward_index(
+    distribution_schedule: &[Schedule],
+    state: &State,
+    global_reward_index: Decimal,
+    time: u64,
+) -> StdResult<Decimal> {
     // if there is first stake, the reward index is 0
     if state.last_updated == 0 {
         return Ok(Decimal::zero());
@@ -218,62 +632,66 @@ fn compute_reward_index(cfg: &Config, state: &State, time: u64) -> StdResult<Dec
 
     // if we are outside distribution schedule then Error
     let (i, j) = find_distribution_schedule_range(
-        &cfg, state.last_updated, time);
+        distribution_schedule, state.last_updated, time);
 
     let mut distributed_amount = Uint128::zero();
 
     for id in i..=j {
-        if id < 0 || id >= cfg.distribution_schedule.len() as i32 {
+        if id < 0 || id >= distribution_schedule.len() as i32 {
             continue;
         }
 
-        let schedule = &cfg.distribution_schedule[id as usize];
-
-        // compute distributed amount per second for current schedule
-        let distributed_amount_per_sec = schedule.amount
-            .div(Uint128::from(schedule.end_time - schedule.start_time));
+        let schedule = &distribution_schedule[id as usize];
 
         // distributed amount per second multiplied by time elapsed in this schedule
-        distributed_amount += distributed_amount_per_sec
+        distributed_amount += amount_per_sec(schedule)
             .mul(Uint128::from(cmp::min(time, schedule.end_time) -
                 cmp::max(state.last_updated, schedule.start_time)));
     }
 
     // global reward index is increased by distributed amount per staked token
     if state.total_stake.is_zero() {
-        Ok(state.global_reward_index)
+        Ok(global_reward_index)
     } else {
-        Ok(state.global_reward_index
+        Ok(global_reward_index
             + Decimal::from_ratio(distributed_amount, state.total_stake))
     }
 }
 
-fn find_distribution_schedule_range(cfg: &Config, start_time: u64, end_time: u64) -> (i32, i32) {
+/// Distributed amount per second for the schedule
+fn amount_per_sec(schedule: &Schedule) -> Uint128 {
+    schedule.amount.div(Uint128::from(schedule.end_time - schedule.start_time))
+}
+
+fn find_distribution_schedule_range(
+    distribution_schedule: &[Schedule],
+    start_time: u64,
+    end_time: u64,
+) -> (i32, i32) {
     let mut start = -1;
     let mut end = -1;
 
-    for (i, schedule) in cfg.distribution_schedule.iter().enumerate() {
+    for (i, schedule) in distribution_schedule.iter().enumerate() {
         if start_time >= schedule.start_time && start_time < schedule.end_time {
             start = i as i32
         } else if start_time >= schedule.end_time {
             start = (i + 1) as i32
         }
 
-        if end_time >= schedule.start_time && end_time < schedule.end_time {
+        // last schedule started by the end time, it may be already finished
+        if end_time >= schedule.start_time {
             end = i as i32
-        } else if start_time >= schedule.end_time {
-            end = (i + 1) as i32
         }
     }
 
     (start, end)
 }
 
-fn compute_member_reward(member_info: &MemberInfo, global_reward_index: Decimal) -> Uint128 {
-    let pending_reward = member_info.stake * global_reward_index
-        - member_info.stake * member_info.reward_index;
+fn compute_member_reward(weight: Uint128, reward: &MemberReward, global_reward_index: Decimal) -> Uint128 {
+    let pending_reward = weight * global_reward_index
+        - weight * reward.reward_index;
 
-    return member_info.pending_reward + pending_reward;
+    reward.pending_reward + pending_reward
 }
 
 pub fn execute_unbond(
@@ -287,37 +705,123 @@ pub fn execute_unbond(
     // sender has to pay fee to unbond
     must_pay_fee(&info, &cfg, "unbond".to_string())?;
 
-    // provide them a claim
-    CLAIMS.create_claim(
-        deps.storage,
-        &info.sender,
-        amount,
-        Duration::Time(cfg.unbonding_period).after(&env.block),
-    )?;
-
+    // compute reward and updates member info with new rewards
     let mut state = STATE.load(deps.storage)?;
+    let mut diffs = update_state(deps.storage, &env, &cfg, &mut state)?;
     let mut member_info = MEMBERS.may_load(deps.storage, &info.sender)?
         .ok_or(ContractError::MemberNotFound {})?;
-
-    // compute reward and updates member info with new rewards
-    update_member_reward(&state, &cfg, env.block.time.seconds(), &mut member_info)?;
+    let time = env.block.time.seconds();
+    let old_weight = member_info.weight();
+    settle_member_reward(deps.storage, &[], &state.global_reward_indices, &mut member_info, time)?;
+    let weight = member_info.weight();
+    auto_compound_member_reward(&cfg, &mut state, &mut member_info);
 
     // update member stake
     member_info.stake = member_info.stake.checked_sub(amount).map_err(StdError::overflow)?;
 
+    // locked tokens are unbonded only when there is not enough unlocked stake
+    let mut penalty = Uint128::zero();
+    let locked_amount = member_info.locked().checked_sub(member_info.stake).unwrap_or_default();
+    if !locked_amount.is_zero() {
+        match cfg.early_unlock {
+            EarlyUnlock::Forbidden {} => return Err(ContractError::StakeLocked {}),
+            EarlyUnlock::Penalty { percentage } => {
+                break_locks(deps.storage, &mut member_info, locked_amount)?;
+                penalty = locked_amount.multiply_ratio(percentage, 100u128);
+            }
+        }
+    }
+
+    // provide them a claim
+    CLAIMS.create_claim(
+        deps.storage,
+        &info.sender,
+        amount - penalty,
+        Duration::Time(cfg.unbonding_period).after(&env.block),
+    )?;
 
-    // update state with new stake and global_reward_index
-    state.total_stake -= amount;
-    state.last_updated = env.block.time.seconds();
-    state.global_reward_index = member_info.reward_index;
+    // update state with new stake
+    state.total_stake = state.total_stake - weight + member_info.weight();
 
     // save new member info and state in storage
     MEMBERS.save(deps.storage, &info.sender, &member_info)?;
     STATE.save(deps.storage, &state)?;
+    snapshot_stake(deps.storage, &env, &info.sender, member_info.weight(), state.total_stake)?;
+
+    diffs.push(member_diff(&info.sender, Some(old_weight), member_info.weight()));
+    let mut response = Response::new()
+        .add_submessages(member_changed_hooks(deps.storage, diffs)?);
+    if !penalty.is_zero() {
+        // create message to transfer early unlock penalty to burn address
+        response = response.add_submessage(transfer_msg(&cfg.staking_token, &cfg.burn_address, penalty)?);
+    }
 
-    Ok(Response::new()
+    Ok(response
         .add_attribute("action", "unbond")
         .add_attribute("amount", amount)
+        .add_attribute("penalty", penalty)
+        .add_attribute("sender", info.sender))
+}
+
+/// Removes locks which already ended, so they no longer boost member weight
+fn release_expired_locks(member_info: &mut MemberInfo, time: u64) {
+    member_info.locks.retain(|lock| lock.unlock_at > time);
+}
+
+/// Releases given amount from member locks, the ones ending first are released first
+fn break_locks(storage: &mut dyn Storage, member_info: &mut MemberInfo, mut amount: Uint128) -> StdResult<()> {
+    member_info.locks.sort_by_key(|lock| lock.unlock_at);
+    for lock in member_info.locks.iter_mut() {
+        let released = cmp::min(lock.amount, amount);
+        if released.is_zero() {
+            break;
+        }
+        let boost = lock.boost();
+        lock.amount -= released;
+        amount -= released;
+
+        // released boost no longer ends at the unlock time
+        let released_boost = boost - lock.boost();
+        LOCK_EXPIRIES.update(storage, U64Key::new(lock.unlock_at), |expiring| -> StdResult<_> {
+            Ok(expiring.unwrap_or_default().checked_sub(released_boost).unwrap_or_default())
+        })?;
+    }
+    member_info.locks.retain(|lock| !lock.amount.is_zero());
+    Ok(())
+}
+
+pub fn execute_rebond(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+
+    // sender has to pay fee to rebond
+    must_pay_fee(&info, &cfg, "rebond".to_string())?;
+
+    // all pending claims are rebonded by default
+    let pending: Uint128 = CLAIMS.query_claims(deps.as_ref(), &info.sender)?.claims
+        .iter()
+        .map(|claim| claim.amount)
+        .sum();
+    let amount = amount.unwrap_or(pending);
+    if amount.is_zero() {
+        return Err(ContractError::NothingToClaim {});
+    }
+    if amount > pending {
+        return Err(ContractError::NotEnoughClaims {});
+    }
+
+    CLAIMS.rebond(deps.storage, &info.sender, amount)?;
+    let diffs = stake_tokens(deps.storage, &env, &cfg, &info.sender, amount, None)?;
+    let messages = member_changed_hooks(deps.storage, diffs)?;
+
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "rebond")
+        .add_attribute("amount", amount)
         .add_attribute("sender", info.sender))
 }
 
@@ -325,6 +829,7 @@ pub fn execute_claim(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
+    amount: Option<Uint128>,
 ) -> Result<Response, ContractError> {
     let cfg = CONFIG.load(deps.storage)?;
 
@@ -332,25 +837,19 @@ pub fn execute_claim(
     must_pay_fee(&info, &cfg, "claim".to_string())?;
 
     // get amount of tokens to release
-    let release = CLAIMS.claim_tokens(deps.storage, &info.sender, &env.block, None)?;
-    if release.is_zero() {
-        return Err(ContractError::NothingToClaim {});
-    }
+    validate_claim_amount(deps.as_ref(), &info.sender, &env.block, amount)?;
+    let release = CLAIMS.claim_tokens(deps.storage, &info.sender, &env.block, amount)?
+        .iter()
+        .map(|claim| claim.amount)
+        .sum();
 
     // create message to transfer staking tokens
-    let message = SubMsg::new(WasmMsg::Execute {
-        contract_addr: cfg.staking_token.clone().into(),
-        msg: to_binary(&Cw20ExecuteMsg::Transfer {
-            recipient: info.sender.clone().into(),
-            amount: release,
-        })?,
-        funds: vec![],
-    });
+    let message = transfer_msg(&cfg.staking_token, &info.sender, release)?;
 
     Ok(Response::new()
         .add_submessage(message)
         .add_attribute("action", "claim")
-        .add_attribute("tokens", coin_to_string(release, cfg.staking_token.as_str()))
+        .add_attribute("tokens", coin_to_string(release, denom_to_str(&cfg.staking_token)))
         .add_attribute("sender", info.sender))
 }
 
@@ -358,60 +857,147 @@ pub fn execute_instant_claim(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
+    amount: Option<Uint128>,
 ) -> Result<Response, ContractError> {
     let cfg = CONFIG.load(deps.storage)?;
 
     // sender has to pay fee to instant claim
     must_pay_fee(&info, &cfg, "instant_claim".to_string())?;
 
-    let config = CONFIG.load(deps.storage)?;
-
-    // Create block after unbonding_period to be able to release all claims
-    let mut block = env.block.clone();
-    block.time = block.time.plus_seconds(YEAR_IN_SEC);
-
     // get amount of tokens to release
-    let mut release = CLAIMS.claim_tokens(deps.storage, &info.sender, &block, None)?;
-    if release.is_zero() {
-        return Err(ContractError::NothingToClaim {});
-    }
+    let block = instant_claim_block(&env);
+    validate_claim_amount(deps.as_ref(), &info.sender, &block, amount)?;
+    let claims = CLAIMS.claim_tokens(deps.storage, &info.sender, &block, amount)?;
 
-    // calculate fee for instant claim
-    let fee = release
-        .checked_mul(Uint128::from(config.instant_claim_percentage_loss))
-        .map_err(StdError::overflow)?
-        .div(Uint128::new(100));
-    release = release.checked_sub(fee)
-        .map_err(StdError::overflow)?;
+    // calculate fee for instant claim on the released claims only
+    let (release, fee) = instant_claim_amounts(&cfg, &claims, &env.block);
+
+    // split fee between stakers, treasury and burn address
+    let mut state = STATE.load(deps.storage)?;
+    let diffs = update_state(deps.storage, &env, &cfg, &mut state)?;
+    let (burned, treasury, redistributed) = split_instant_claim_fee(&cfg, fee);
+    redistribute_to_stakers(&cfg, &mut state, redistributed)?;
+    STATE.save(deps.storage, &state)?;
 
     // create message to release staking tokens to owner
-    let message1 = SubMsg::new(WasmMsg::Execute {
-        contract_addr: config.staking_token.clone().into(),
-        msg: to_binary(&Cw20ExecuteMsg::Transfer {
-            recipient: info.sender.clone().into(),
-            amount: release,
-        })?,
-        funds: vec![],
-    });
+    let mut messages = vec![transfer_msg(&cfg.staking_token, &info.sender, release)?];
 
-    // create message to transfer fee to burn address
-    let message2 = SubMsg::new(WasmMsg::Execute {
-        contract_addr: config.staking_token.clone().into(),
-        msg: to_binary(&Cw20ExecuteMsg::Transfer {
-            recipient: config.burn_address.clone().into(),
-            amount: fee,
-        })?,
-        funds: vec![],
-    });
+    // create messages to transfer fee to burn and treasury addresses
+    if !burned.is_zero() {
+        messages.push(transfer_msg(&cfg.staking_token, &cfg.burn_address, burned)?);
+    }
+    if let (Some(treasury_address), false) = (&cfg.treasury_address, treasury.is_zero()) {
+        messages.push(transfer_msg(&cfg.staking_token, treasury_address, treasury)?);
+    }
 
+    let denom = denom_to_str(&cfg.staking_token);
     Ok(Response::new()
-        .add_submessages([message1, message2])
+        .add_submessages(messages)
+        .add_submessages(member_changed_hooks(deps.storage, diffs)?)
         .add_attribute("action", "instant_claim")
-        .add_attribute("tokens", coin_to_string(release, config.staking_token.as_str()))
-        .add_attribute("fee", coin_to_string(fee, config.staking_token.as_str()))
+        .add_attribute("tokens", coin_to_string(release, denom))
+        .add_attribute("fee", coin_to_string(fee, denom))
+        .add_attribute("burned", coin_to_string(burned, denom))
+        .add_attribute("treasury", coin_to_string(treasury, denom))
+        .add_attribute("redistributed", coin_to_string(redistributed, denom))
         .add_attribute("sender", info.sender))
 }
 
+/// Block after the unbonding period, so instant claim releases all pending claims
+fn instant_claim_block(env: &Env) -> BlockInfo {
+    let mut block = env.block.clone();
+    block.time = block.time.plus_seconds(YEAR_IN_SEC);
+    block
+}
+
+/// Splits instant claim fee into burned, treasury and redistributed amounts
+fn split_instant_claim_fee(cfg: &Config, fee: Uint128) -> (Uint128, Uint128, Uint128) {
+    let split = &cfg.instant_claim_split;
+    let redistributed = fee.multiply_ratio(split.stakers, 100u128);
+    let treasury = fee.multiply_ratio(split.treasury, 100u128);
+    (fee - treasury - redistributed, treasury, redistributed)
+}
+
+/// Adds the amount to rewards of the stream paid in the staking token. While there is no stake
+/// it is kept for the next stakers
+fn redistribute_to_stakers(cfg: &Config, state: &mut State, amount: Uint128) -> Result<(), ContractError> {
+    if amount.is_zero() {
+        return Ok(());
+    }
+    let stream = compound_stream(cfg).ok_or(ContractError::StakersStreamNotFound {})?;
+
+    // redistributed tokens stay in the contract and back the rewards
+    state.reward_reserve_mut(stream).redistributed += amount;
+    state.undistributed += amount;
+    distribute_to_stakers(cfg, state);
+    Ok(())
+}
+
+/// Raises reward index of the stream paid in the staking token by the undistributed amount,
+/// so it is shared by current stakers
+fn distribute_to_stakers(cfg: &Config, state: &mut State) {
+    let stream = match compound_stream(cfg) {
+        Some(stream) if !state.undistributed.is_zero() && !state.total_stake.is_zero() => stream,
+        _ => return,
+    };
+
+    if state.global_reward_indices.len() <= stream {
+        state.global_reward_indices.resize(stream + 1, Decimal::zero());
+    }
+    state.global_reward_indices[stream] = state.global_reward_indices[stream]
+        + Decimal::from_ratio(state.undistributed, state.total_stake);
+    state.undistributed = Uint128::zero();
+}
+
+/// Splits released claims into amount paid out and instant claim fee.
+/// Fee of every claim follows the configured curve of its remaining unbonding time
+fn instant_claim_amounts(cfg: &Config, claims: &[Claim], block: &BlockInfo) -> (Uint128, Uint128) {
+    let now = block.time.seconds();
+    let mut release = Uint128::zero();
+    let mut fee = Uint128::zero();
+    for claim in claims {
+        let remaining = match (&cfg.instant_claim_curve, claim.release_at) {
+            (InstantClaimCurve::Flat {}, _) => cfg.unbonding_period,
+            (InstantClaimCurve::Linear {}, Expiration::AtTime(time)) =>
+                cmp::min(time.seconds().saturating_sub(now), cfg.unbonding_period),
+            _ if claim.release_at.is_expired(block) => 0,
+            _ => cfg.unbonding_period,
+        };
+        let claim_fee = if cfg.unbonding_period == 0 {
+            Uint128::zero()
+        } else {
+            claim.amount.multiply_ratio(
+                u128::from(cfg.instant_claim_percentage_loss) * u128::from(remaining),
+                100u128 * u128::from(cfg.unbonding_period),
+            )
+        };
+        release += claim.amount - claim_fee;
+        fee += claim_fee;
+    }
+    (release, fee)
+}
+
+/// Checks the requested amount against the claims which can be released at the block
+fn validate_claim_amount(
+    deps: Deps,
+    addr: &Addr,
+    block: &BlockInfo,
+    amount: Option<Uint128>,
+) -> Result<(), ContractError> {
+    let releasable: Uint128 = CLAIMS.query_claims(deps, addr)?.claims
+        .iter()
+        .filter(|claim| claim.release_at.is_expired(block))
+        .map(|claim| claim.amount)
+        .sum();
+    if releasable.is_zero() || amount == Some(Uint128::zero()) {
+        return Err(ContractError::NothingToClaim {});
+    }
+    if matches!(amount, Some(amount) if amount > releasable) {
+        return Err(ContractError::NotEnoughClaims {});
+    }
+    Ok(())
+}
+
 pub fn execute_withdraw(
     deps: DepsMut,
     env: Env,
@@ -422,49 +1008,181 @@ pub fn execute_withdraw(
     // sender has to pay fee to withdraw
     must_pay_fee(&info, &cfg, "withdraw".to_string())?;
 
-    let state = STATE.load(deps.storage)?;
+    // calculate member reward until current block or end of distribution
+    let mut state = STATE.load(deps.storage)?;
+    let diffs = update_state(deps.storage, &env, &cfg, &mut state)?;
     let mut member_info = MEMBERS.may_load(deps.storage, &info.sender)?
-        .unwrap_or(Default::default());
+        .unwrap_or_default();
+    let time = env.block.time.seconds();
+    settle_member_reward(deps.storage, &[], &state.global_reward_indices, &mut member_info, time)?;
+
+    let mut messages = vec![];
+    let mut tokens = vec![];
+    for (i, (stream, reward)) in cfg.reward_streams.iter().zip(member_info.rewards.iter_mut()).enumerate() {
+        // amount to withdraw is difference between the reward and the withdraw amount
+        let amount = reward.pending_reward.checked_sub(reward.withdrawn)
+            .map_err(StdError::overflow)?;
+        if amount.is_zero() {
+            continue;
+        }
 
-    // calculate member reward until current block or end of distribution
-    update_member_reward(&state, &cfg, env.block.time.seconds(), &mut member_info)?;
+        // update withdrawal
+        reward.withdrawn += amount;
+        state.reward_reserve_mut(i).paid += amount;
 
-    // amount to withdraw is difference between the reward and the withdraw amount
-    let amount = member_info.pending_reward.checked_sub(member_info.withdrawn)
-        .map_err(StdError::overflow)?;
+        messages.push(transfer_msg(&stream.token, &info.sender, amount)?);
+        tokens.push(coin_to_string(amount, denom_to_str(&stream.token)));
+    }
 
-    if amount.is_zero() {
+    if messages.is_empty() {
         return Err(ContractError::NothingToWithdraw {});
     }
 
-    // update withdrawal
-    MEMBERS.update(deps.storage, &info.sender, |member_info| -> StdResult<_> {
-        let mut info = member_info.unwrap();
-        info.withdrawn += amount;
-        Ok(info)
-    })?;
-
-    // create message to transfer reward in terraland tokens
-    let config = CONFIG.load(deps.storage)?;
-    let message = SubMsg::new(WasmMsg::Execute {
-        contract_addr: config.terraland_token.to_string(),
-        msg: to_binary(&Cw20ExecuteMsg::Transfer {
-            recipient: info.sender.clone().into(),
-            amount,
-        })?,
-        funds: vec![],
-    });
+    MEMBERS.save(deps.storage, &info.sender, &member_info)?;
+    STATE.save(deps.storage, &state)?;
 
     Ok(Response::new()
-        .add_submessage(message)
+        .add_submessages(messages)
+        .add_submessages(member_changed_hooks(deps.storage, diffs)?)
         .add_attribute("action", "withdraw")
-        .add_attribute("tokens", coin_to_string(amount, config.terraland_token.as_str()))
+        .add_attribute("tokens", tokens.join(", "))
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_compound(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+
+    // sender has to pay fee to compound
+    must_pay_fee(&info, &cfg, "compound".to_string())?;
+
+    let stream = compound_stream(&cfg).ok_or(ContractError::CompoundNotSupported {})?;
+
+    // compute reward and updates member info with new rewards
+    let mut state = STATE.load(deps.storage)?;
+    let mut diffs = update_state(deps.storage, &env, &cfg, &mut state)?;
+    let mut member_info = MEMBERS.may_load(deps.storage, &info.sender)?
+        .ok_or(ContractError::MemberNotFound {})?;
+    let time = env.block.time.seconds();
+    let old_weight = member_info.weight();
+    settle_member_reward(deps.storage, &[], &state.global_reward_indices, &mut member_info, time)?;
+    let weight = member_info.weight();
+
+    let amount = compound_member_reward(stream, &mut state, &mut member_info);
+    if amount.is_zero() {
+        return Err(ContractError::NothingToWithdraw {});
+    }
+
+    // update state with new stake
+    state.total_stake = state.total_stake - weight + member_info.weight();
+
+    MEMBERS.save(deps.storage, &info.sender, &member_info)?;
+    STATE.save(deps.storage, &state)?;
+    snapshot_stake(deps.storage, &env, &info.sender, member_info.weight(), state.total_stake)?;
+
+    diffs.push(member_diff(&info.sender, Some(old_weight), member_info.weight()));
+    let messages = member_changed_hooks(deps.storage, diffs)?;
+
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "compound")
+        .add_attribute("amount", amount)
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_set_auto_compound(
+    deps: DepsMut,
+    info: MessageInfo,
+    enabled: bool,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    if enabled && compound_stream(&cfg).is_none() {
+        return Err(ContractError::CompoundNotSupported {});
+    }
+
+    MEMBERS.update(deps.storage, &info.sender, |member_info| -> Result<_, ContractError> {
+        let mut member_info = member_info.ok_or(ContractError::MemberNotFound {})?;
+        member_info.auto_compound = enabled;
+        Ok(member_info)
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_auto_compound")
+        .add_attribute("enabled", enabled.to_string())
+        .add_attribute("sender", info.sender))
+}
+
+/// Returns the reward stream paying in the staking token, its rewards can be compounded
+fn compound_stream(cfg: &Config) -> Option<usize> {
+    cfg.reward_streams.iter().position(|stream| stream.token == cfg.staking_token)
+}
+
+/// Moves not withdrawn reward of the stream into member stake, returns compounded amount
+fn compound_member_reward(stream: usize, state: &mut State, member_info: &mut MemberInfo) -> Uint128 {
+    let reward = &mut member_info.rewards[stream];
+    let amount = reward.pending_reward - reward.withdrawn;
+
+    reward.withdrawn += amount;
+    member_info.stake += amount;
+    state.reward_reserve_mut(stream).compounded += amount;
+
+    amount
+}
+
+fn auto_compound_member_reward(cfg: &Config, state: &mut State, member_info: &mut MemberInfo) {
+    if let Some(stream) = compound_stream(cfg) {
+        if member_info.auto_compound {
+            compound_member_reward(stream, state, member_info);
+        }
+    }
+}
+
+pub fn execute_add_hook(
+    deps: DepsMut,
+    info: MessageInfo,
+    addr: String,
+) -> Result<Response, ContractError> {
+    // authorized owner
+    let cfg = CONFIG.load(deps.storage)?;
+    if info.sender != cfg.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let hook = deps.api.addr_validate(&addr)?;
+    HOOKS.add_hook(deps.storage, hook.clone())?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_hook")
+        .add_attribute("hook", hook)
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_remove_hook(
+    deps: DepsMut,
+    info: MessageInfo,
+    addr: String,
+) -> Result<Response, ContractError> {
+    // authorized owner
+    let cfg = CONFIG.load(deps.storage)?;
+    if info.sender != cfg.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let hook = deps.api.addr_validate(&addr)?;
+    HOOKS.remove_hook(deps.storage, hook.clone())?;
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_hook")
+        .add_attribute("hook", hook)
         .add_attribute("sender", info.sender))
 }
 
 pub fn execute_ust_withdraw(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     recipient: String,
     amount: Uint128,
@@ -475,6 +1193,15 @@ pub fn execute_ust_withdraw(
         return Err(ContractError::Unauthorized {});
     }
 
+    // ust reserved for rewards cannot be withdrawn
+    let denom = Denom::Native("uusd".to_string());
+    let balance = deps.querier.query_balance(&env.contract.address, "uusd")?.amount;
+    let mut state = STATE.load(deps.storage)?;
+    if amount > release_unreserved(&cfg, &mut state, &denom, balance)? {
+        return Err(ContractError::ReservedFunds {});
+    }
+    STATE.save(deps.storage, &state)?;
+
     // create message to transfer ust
     let message = SubMsg::new(BankMsg::Send {
         to_address: String::from(deps.api.addr_validate(&recipient)?),
@@ -510,12 +1237,20 @@ pub fn execute_token_withdraw(
     }.into();
     let res: BalanceResponse = deps.querier.query(&query)?;
 
+    // tokens reserved for rewards stay in the contract
+    let mut state = STATE.load(deps.storage)?;
+    let amount = release_unreserved(&cfg, &mut state, &Denom::Cw20(token_addr.clone()), res.balance)?;
+    if amount.is_zero() {
+        return Err(ContractError::ReservedFunds {});
+    }
+    STATE.save(deps.storage, &state)?;
+
     // create message to transfer tokens
     let message = SubMsg::new(WasmMsg::Execute {
         contract_addr: token_addr.to_string(),
         msg: to_binary(&Cw20ExecuteMsg::Transfer {
             recipient: String::from(deps.api.addr_validate(&recipient)?),
-            amount: res.balance,
+            amount,
         })?,
         funds: vec![],
     });
@@ -526,6 +1261,32 @@ pub fn execute_token_withdraw(
         .add_attribute("sender", info.sender))
 }
 
+/// Returns part of the token balance not owed to stakers. Funded amounts of the streams
+/// paying in the token are reduced to their schedules, as the surplus can be withdrawn
+fn release_unreserved(
+    cfg: &Config,
+    state: &mut State,
+    token: &Denom,
+    balance: Uint128,
+) -> Result<Uint128, ContractError> {
+    // staking token backs the stakes and claims
+    if *token == cfg.staking_token {
+        return Err(ContractError::ReservedFunds {});
+    }
+
+    let mut owed = Uint128::zero();
+    for (i, stream) in cfg.reward_streams.iter().enumerate() {
+        if stream.token != *token {
+            continue;
+        }
+        let scheduled = scheduled_amount(stream);
+        let reserve = state.reward_reserve_mut(i);
+        owed += reserve.owed(scheduled);
+        reserve.funded = cmp::min(reserve.funded, scheduled);
+    }
+    Ok(balance.checked_sub(owed).unwrap_or_default())
+}
+
 fn must_pay_fee(info: &MessageInfo, cfg: &Config, operation: String) -> Result<(), ContractError> {
     let mut denom = "".to_string();
     let mut fee_amount = Uint128::zero();
@@ -550,6 +1311,128 @@ fn must_pay_fee(info: &MessageInfo, cfg: &Config, operation: String) -> Result<(
     Ok(())
 }
 
+fn validate_reward_streams(
+    api: &dyn Api,
+    streams: Vec<RewardStream>,
+) -> Result<Vec<RewardStream>, ContractError> {
+    streams.into_iter()
+        .map(|stream| {
+            validate_schedules(&stream.distribution_schedule)?;
+            Ok(RewardStream { token: validate_denom(api, stream.token)?, ..stream })
+        })
+        .collect()
+}
+
+fn validate_denom(api: &dyn Api, denom: Denom) -> StdResult<Denom> {
+    match denom {
+        Denom::Cw20(addr) => Ok(Denom::Cw20(api.addr_validate(addr.as_str())?)),
+        native => Ok(native),
+    }
+}
+
+/// Schedules have to be ordered, not overlapping and have positive duration
+pub(crate) fn validate_schedules(schedules: &[Schedule]) -> Result<(), ContractError> {
+    if schedules.iter().any(|schedule| schedule.end_time <= schedule.start_time) {
+        return Err(ContractError::InvalidSchedulePeriod {});
+    }
+    if schedules.windows(2).any(|pair| pair[0].end_time > pair[1].start_time) {
+        return Err(ContractError::OverlappingSchedules {});
+    }
+    Ok(())
+}
+
+/// Schedules which already started cannot change, the new ones have to start in the future
+fn validate_schedule_update(current: &[Schedule], new: &[Schedule], time: u64) -> Result<(), ContractError> {
+    let started = current.iter().take_while(|schedule| schedule.start_time < time).count();
+    if new.len() < started
+        || current[..started] != new[..started]
+        || new[started..].iter().any(|schedule| schedule.start_time < time) {
+        return Err(ContractError::ScheduleAlreadyStarted {});
+    }
+    Ok(())
+}
+
+/// Schedules of every stream cannot distribute more than was funded
+fn validate_funding(streams: &[RewardStream], state: &State) -> Result<(), ContractError> {
+    for (i, stream) in streams.iter().enumerate() {
+        if scheduled_amount(stream) > state.reward_reserve(i).funded {
+            return Err(ContractError::UnderfundedSchedule(i as u32));
+        }
+    }
+    Ok(())
+}
+
+fn scheduled_amount(stream: &RewardStream) -> Uint128 {
+    stream.distribution_schedule.iter().map(|schedule| schedule.amount).sum()
+}
+
+/// Stores reward indices accumulated up to the block time, returns the changes of member weights
+fn checkpoint_reward_indices(storage: &mut dyn Storage, env: &Env, cfg: &Config) -> StdResult<Vec<MemberDiff>> {
+    let mut state = STATE.load(storage)?;
+    let diffs = update_state(storage, env, cfg, &mut state)?;
+    STATE.save(storage, &state)?;
+    Ok(diffs)
+}
+
+fn transfer_msg(token: &Denom, recipient: &Addr, amount: Uint128) -> StdResult<SubMsg> {
+    Ok(match token {
+        Denom::Native(denom) => SubMsg::new(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![Coin { denom: denom.clone(), amount }],
+        }),
+        Denom::Cw20(addr) => SubMsg::new(WasmMsg::Execute {
+            contract_addr: addr.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        }),
+    })
+}
+
+fn validate_lock_tiers(lock_tiers: Vec<LockTier>) -> Result<Vec<LockTier>, ContractError> {
+    if lock_tiers.iter().any(|tier| tier.duration == 0 || tier.multiplier < Decimal::one()) {
+        return Err(ContractError::InvalidLockTier {});
+    }
+    Ok(lock_tiers)
+}
+
+fn validate_penalty_split(
+    split: &PenaltySplit,
+    treasury_address: &Option<Addr>,
+    pays_staking_token: bool,
+) -> Result<(), ContractError> {
+    let total = split.burn.checked_add(split.treasury)
+        .and_then(|total| total.checked_add(split.stakers));
+    if total != Some(100) {
+        return Err(ContractError::InvalidPenaltySplit {});
+    }
+    if split.treasury != 0 && treasury_address.is_none() {
+        return Err(ContractError::TreasuryNotSet {});
+    }
+    // stakers share is redistributed by the stream paid in the staking token
+    if split.stakers != 0 && !pays_staking_token {
+        return Err(ContractError::StakersStreamNotFound {});
+    }
+    Ok(())
+}
+
+fn validate_early_unlock(early_unlock: EarlyUnlock) -> Result<EarlyUnlock, ContractError> {
+    if matches!(early_unlock, EarlyUnlock::Penalty { percentage } if percentage > 100) {
+        return Err(ContractError::InvalidEarlyUnlockPenalty {});
+    }
+    Ok(early_unlock)
+}
+
+#[inline]
+fn denom_to_str(denom: &Denom) -> &str {
+    match denom {
+        Denom::Native(denom) => denom,
+        Denom::Cw20(addr) => addr.as_str(),
+    }
+}
+
 #[inline]
 fn coin_to_string(amount: Uint128, denom: &str) -> String {
     format!("{} {}", amount, denom)
@@ -559,90 +1442,293 @@ fn coin_to_string(amount: Uint128, denom: &str) -> String {
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::PendingOwner {} => to_binary(&query_pending_owner(deps)?),
         QueryMsg::State {} => to_binary(&query_state(deps)?),
-        QueryMsg::Member { address } => to_binary(&query_member(deps, env, address)?),
+        QueryMsg::Staker { address } => to_binary(&query_staker(deps, env, address)?),
+        QueryMsg::ListStakers { start_after, limit } =>
+            to_binary(&query_staker_list(deps, env, start_after, limit)?),
+        QueryMsg::Apr {} => to_binary(&query_apr(deps, env)?),
+        QueryMsg::SimulateReward { address, at_time } =>
+            to_binary(&query_simulate_reward(deps, address, at_time)?),
+        QueryMsg::DistributionStatus {} => to_binary(&query_distribution_status(deps, env)?),
+        QueryMsg::RewardReserve {} => to_binary(&query_reward_reserve(deps)?),
+        QueryMsg::Member { addr, at_height } => to_binary(&query_member(deps, env, addr, at_height)?),
         QueryMsg::ListMembers { start_after, limit } =>
             to_binary(&query_member_list(deps, env, start_after, limit)?),
+        QueryMsg::TotalWeight {} => to_binary(&query_total_weight(deps, env)?),
+        QueryMsg::Hooks {} => to_binary(&HOOKS.query_hooks(deps)?),
+        QueryMsg::StakeAtHeight { address, height } =>
+            to_binary(&query_stake_at_height(deps, address, height)?),
+        QueryMsg::TotalStakeAtHeight { height } =>
+            to_binary(&query_total_stake_at_height(deps, height)?),
+        QueryMsg::SimulateInstantClaim { address, amount } =>
+            to_binary(&query_simulate_instant_claim(deps, env, address, amount)?),
     }
 }
 
 pub fn query_config(deps: Deps) -> StdResult<Config> {
-    Ok(CONFIG.load(deps.storage)?)
+    CONFIG.load(deps.storage)
 }
 
 fn query_state(deps: Deps) -> StdResult<State> {
-    Ok(STATE.load(deps.storage)?)
+    STATE.load(deps.storage)
 }
 
-fn query_member(deps: Deps, env: Env, addr: String) -> StdResult<MemberResponse> {
+fn query_staker(deps: Deps, env: Env, addr: String) -> StdResult<StakerResponse> {
     let addr = deps.api.addr_validate(&addr)?;
     let member_info = MEMBERS.may_load(deps.storage, &addr)?;
 
     if let Some(mut info) = member_info {
         let cfg = CONFIG.load(deps.storage)?;
-        let state = STATE.load(deps.storage)?;
-        update_member_reward(&state, &cfg, env.block.time.seconds(), &mut info)?;
+        let mut state = STATE.load(deps.storage)?;
+        let time = env.block.time.seconds();
+        let unlocked = advance_state(deps.storage, &cfg, &mut state, time)?;
+        settle_member_reward(deps.storage, &unlocked, &state.global_reward_indices, &mut info, time)?;
 
-        return Ok(MemberResponse {
-            member: Some(MemberResponseItem {
+        return Ok(StakerResponse {
+            member: Some(StakerResponseItem {
                 stake: info.stake,
-                reward: info.pending_reward,
-                reward_index: info.reward_index,
-                withdrawn: info.withdrawn,
+                rewards: member_rewards(&cfg, &info),
+                auto_compound: info.auto_compound,
+                locks: info.locks,
                 claims: CLAIMS.query_claims(deps, &addr)?.claims,
             }),
         });
     }
 
-    Ok(MemberResponse { member: None })
+    Ok(StakerResponse { member: None })
+}
+
+fn query_apr(deps: Deps, env: Env) -> StdResult<AprResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let mut state = STATE.load(deps.storage)?;
+    let time = env.block.time.seconds();
+    advance_state(deps.storage, &cfg, &mut state, time)?;
+
+    let streams = cfg.reward_streams.into_iter()
+        .map(|stream| {
+            // yearly amount of the schedules active right now per staked token
+            let yearly_amount = stream.distribution_schedule.iter()
+                .filter(|schedule| schedule.start_time <= time && time < schedule.end_time)
+                .map(|schedule| amount_per_sec(schedule) * Uint128::from(YEAR_IN_SEC))
+                .sum::<Uint128>();
+            let apr = if state.total_stake.is_zero() {
+                Decimal::zero()
+            } else {
+                Decimal::from_ratio(yearly_amount, state.total_stake)
+            };
+            StreamAprResponse { token: stream.token, apr }
+        })
+        .collect();
+
+    Ok(AprResponse { streams })
+}
+
+fn query_simulate_reward(deps: Deps, address: String, at_time: u64) -> StdResult<SimulateRewardResponse> {
+    let addr = deps.api.addr_validate(&address)?;
+    let cfg = CONFIG.load(deps.storage)?;
+    let mut state = STATE.load(deps.storage)?;
+    if at_time < state.last_updated {
+        return Err(StdError::generic_err("Cannot simulate reward before last state update"));
+    }
+
+    let mut info = MEMBERS.may_load(deps.storage, &addr)?.unwrap_or_default();
+    let unlocked = advance_state(deps.storage, &cfg, &mut state, at_time)?;
+    settle_member_reward(deps.storage, &unlocked, &state.global_reward_indices, &mut info, at_time)?;
+
+    Ok(SimulateRewardResponse { rewards: member_rewards(&cfg, &info) })
+}
+
+fn query_distribution_status(deps: Deps, env: Env) -> StdResult<DistributionStatusResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let time = env.block.time.seconds();
+
+    let streams = cfg.reward_streams.into_iter()
+        .map(|stream| {
+            let schedules = stream.distribution_schedule.iter()
+                .map(|schedule| {
+                    let amount_per_sec = amount_per_sec(schedule);
+                    let elapsed = cmp::min(cmp::max(time, schedule.start_time), schedule.end_time)
+                        - schedule.start_time;
+                    let distributed = amount_per_sec * Uint128::from(elapsed);
+                    ScheduleStatus {
+                        amount: schedule.amount,
+                        start_time: schedule.start_time,
+                        end_time: schedule.end_time,
+                        amount_per_sec,
+                        distributed,
+                        remaining: schedule.amount - distributed,
+                    }
+                })
+                .collect();
+            StreamDistributionStatus { token: stream.token, schedules }
+        })
+        .collect();
+
+    Ok(DistributionStatusResponse { streams })
+}
+
+fn query_reward_reserve(deps: Deps) -> StdResult<RewardReserveResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let state = STATE.load(deps.storage)?;
+
+    let streams = cfg.reward_streams.into_iter().enumerate()
+        .map(|(i, stream)| {
+            let reserve = state.reward_reserve(i);
+            let scheduled = scheduled_amount(&stream);
+            StreamReserveResponse {
+                token: stream.token,
+                funded: reserve.funded,
+                scheduled,
+                redistributed: reserve.redistributed,
+                paid: reserve.paid,
+                compounded: reserve.compounded,
+                owed: reserve.owed(scheduled),
+            }
+        })
+        .collect();
+
+    Ok(RewardReserveResponse { streams })
+}
+
+fn member_rewards(cfg: &Config, info: &MemberInfo) -> Vec<MemberRewardResponse> {
+    cfg.reward_streams.iter().zip(info.rewards.iter())
+        .map(|(stream, reward)| MemberRewardResponse {
+            token: stream.token.clone(),
+            reward: reward.pending_reward,
+            reward_index: reward.reward_index,
+            withdrawn: reward.withdrawn,
+        })
+        .collect()
 }
 
 // settings for pagination
 const MAX_LIMIT: u32 = 30;
 const DEFAULT_LIMIT: u32 = 10;
 
-fn query_member_list(
+fn query_staker_list(
     deps: Deps,
     env: Env,
     start_after: Option<String>,
     limit: Option<u32>,
-) -> StdResult<MemberListResponse> {
+) -> StdResult<StakerListResponse> {
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
     let addr = maybe_addr(deps.api, start_after)?;
     let start = addr.map(|addr| Bound::exclusive(addr.as_ref()));
 
+    let cfg = CONFIG.load(deps.storage)?;
+    let mut state = STATE.load(deps.storage)?;
+    let time = env.block.time.seconds();
+    let unlocked = advance_state(deps.storage, &cfg, &mut state, time)?;
+
     let members: StdResult<Vec<_>> = MEMBERS
         .range(deps.storage, start, None, Order::Ascending)
         .take(limit)
         .map(|item| {
             let (key, mut info) = item?;
             let address = deps.api.addr_validate(&String::from_utf8(key)?)?;
-            let cfg = CONFIG.load(deps.storage)?;
-            let state = STATE.load(deps.storage)?;
-
-            update_member_reward(&state, &cfg, env.block.time.seconds(), &mut info)?;
+            settle_member_reward(deps.storage, &unlocked, &state.global_reward_indices, &mut info, time)?;
 
-            Ok(MemberListResponseItem {
+            Ok(StakerListResponseItem {
                 address: address.to_string(),
-                info: MemberResponseItem {
+                info: StakerResponseItem {
                     stake: info.stake,
-                    reward: info.pending_reward,
-                    reward_index: info.reward_index,
-                    withdrawn: info.withdrawn,
+                    rewards: member_rewards(&cfg, &info),
+                    auto_compound: info.auto_compound,
+                    locks: info.locks,
                     claims: CLAIMS.query_claims(deps, &address)?.claims,
                 },
             })
         })
         .collect();
 
+    Ok(StakerListResponse { members: members? })
+}
+
+fn query_member(deps: Deps, env: Env, addr: String, at_height: Option<u64>) -> StdResult<MemberResponse> {
+    let addr = deps.api.addr_validate(&addr)?;
+    let weight = match at_height {
+        Some(height) => STAKE_SNAPSHOTS.may_load_at_height(deps.storage, &addr, height)?,
+        None => MEMBERS.may_load(deps.storage, &addr)?.map(|mut info| {
+            release_expired_locks(&mut info, env.block.time.seconds());
+            info.weight()
+        }),
+    };
+    Ok(MemberResponse { weight: weight.map(weight_to_u64) })
+}
+
+fn query_member_list(
+    deps: Deps,
+    env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<MemberListResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let addr = maybe_addr(deps.api, start_after)?;
+    let start = addr.map(|addr| Bound::exclusive(addr.as_ref()));
+
+    let members: StdResult<Vec<_>> = MEMBERS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (key, mut info) = item?;
+            release_expired_locks(&mut info, env.block.time.seconds());
+            Ok(Member {
+                addr: String::from_utf8(key)?,
+                weight: weight_to_u64(info.weight()),
+            })
+        })
+        .collect();
+
     Ok(MemberListResponse { members: members? })
 }
 
+fn query_stake_at_height(deps: Deps, address: String, height: u64) -> StdResult<StakeAtHeightResponse> {
+    let addr = deps.api.addr_validate(&address)?;
+    let stake = STAKE_SNAPSHOTS.may_load_at_height(deps.storage, &addr, height)?
+        .unwrap_or_default();
+    Ok(StakeAtHeightResponse { stake, height })
+}
+
+fn query_total_stake_at_height(deps: Deps, height: u64) -> StdResult<TotalStakeAtHeightResponse> {
+    let total_stake = TOTAL_STAKE_SNAPSHOTS.may_load_at_height(deps.storage, TOTAL_STAKE_KEY, height)?
+        .unwrap_or_default();
+    Ok(TotalStakeAtHeightResponse { total_stake, height })
+}
+
+fn query_simulate_instant_claim(
+    deps: Deps,
+    env: Env,
+    address: String,
+    amount: Option<Uint128>,
+) -> StdResult<SimulateInstantClaimResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let addr = deps.api.addr_validate(&address)?;
+    let claims = CLAIMS.releasable_claims(deps, &addr, &instant_claim_block(&env), amount)?;
+    let (amount, fee) = instant_claim_amounts(&cfg, &claims, &env.block);
+
+    let (burned, treasury, redistributed) = split_instant_claim_fee(&cfg, fee);
+    Ok(SimulateInstantClaimResponse { amount, fee, burned, treasury, redistributed })
+}
+
+fn query_total_weight(deps: Deps, env: Env) -> StdResult<TotalWeightResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let mut state = STATE.load(deps.storage)?;
+    advance_state(deps.storage, &cfg, &mut state, env.block.time.seconds())?;
+    Ok(TotalWeightResponse { weight: weight_to_u64(state.total_stake) })
+}
+
 #[cfg(test)]
 mod tests {
-    use cosmwasm_std::{Coin, from_slice};
+    use cosmwasm_std::{attr, Coin, from_slice};
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use crate::state::{FeeConfig, Schedule};
+    use cw0::Expiration;
+    use cw4::{Cw4QueryMsg, HooksResponse};
+    use cw_controllers::HookError;
+    use ownership::{OwnershipError, PendingOwnerResponse};
+
+    use crate::claims::Claim;
+    use crate::state::FeeConfig;
 
     use super::*;
 
@@ -657,26 +1743,20 @@ mod tests {
     const WEEK: u64 = 604800;
 
     fn default_instantiate(
-        deps: DepsMut,
+        mut deps: DepsMut,
         env: Env
     ) {
         let msg = InstantiateMsg {
             owner: INIT_ADMIN.into(),
-            staking_token: STAKING_TOKEN_ADDRESS.into(),
+            staking_token: Denom::Cw20(Addr::unchecked(STAKING_TOKEN_ADDRESS)),
             terraland_token: TERRALAND_TOKEN_ADDRESS.into(),
             unbonding_period: UNBONDING_PERIOD,
             burn_address: BURN_ADDRESS.into(),
             instant_claim_percentage_loss: 0,
-            distribution_schedule: Vec::from([
-                Schedule {
-                    amount: Uint128::new(150_000_000_000),
-                    start_time: env.block.time.seconds(),
-                    end_time: env.block.time.seconds() + WEEK,
-                },
-                Schedule {
-                    amount: Uint128::new(100_000_000_000),
-                    start_time: env.block.time.seconds() + WEEK,
-                    end_time: env.block.time.seconds() + 2 * WEEK,
+            reward_streams: Vec::from([
+                RewardStream {
+                    token: Denom::Cw20(Addr::unchecked(TERRALAND_TOKEN_ADDRESS)),
+                    distribution_schedule: vec![],
                 }
             ]),
             fee_config: Vec::from([
@@ -701,9 +1781,33 @@ mod tests {
                     denom: "uusd".to_string()
                 }
             ]),
+            lock_tiers: vec![LockTier { duration: WEEK, multiplier: Decimal::percent(200) }],
+            early_unlock: EarlyUnlock::Forbidden {},
+            treasury_address: None,
+            instant_claim_split: PenaltySplit::default(),
+            instant_claim_curve: InstantClaimCurve::Linear {},
         };
         let info = mock_info("creator", &[]);
-        instantiate(deps, env, info, msg).unwrap();
+        instantiate(deps.branch(), env.clone(), info, msg).unwrap();
+
+        // schedules can be added only once they are funded
+        fund_rewards(deps.branch(), 250_000_000_000);
+        let schedules = [
+            Schedule {
+                amount: Uint128::new(150_000_000_000),
+                start_time: env.block.time.seconds(),
+                end_time: env.block.time.seconds() + WEEK,
+            },
+            Schedule {
+                amount: Uint128::new(100_000_000_000),
+                start_time: env.block.time.seconds() + WEEK,
+                end_time: env.block.time.seconds() + 2 * WEEK,
+            },
+        ];
+        for schedule in schedules {
+            let msg = ExecuteMsg::AppendSchedule { stream: 0, schedule };
+            execute(deps.branch(), env.clone(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+        }
     }
 
     #[test]
@@ -719,7 +1823,7 @@ mod tests {
         let res = query_state(deps.as_ref()).unwrap();
         assert_eq!(0, res.total_stake.u128());
 
-        let res = query_member(deps.as_ref(), env, USER1.into()).unwrap();
+        let res = query_staker(deps.as_ref(), env, USER1.into()).unwrap();
         assert_eq!(None, res.member)
     }
 
@@ -727,21 +1831,21 @@ mod tests {
         let mut env = mock_env();
         env.block.height += height_delta;
         env.block.time = env.block.time.plus_seconds(height_delta * 6);
-        return env;
+        env
     }
 
-    fn get_member(deps: Deps, addr: String) -> Option<MemberResponseItem> {
-        let raw = query(deps, mock_env(), QueryMsg::Member { address: addr }).unwrap();
-        let res: MemberResponse = from_slice(&raw).unwrap();
-        return res.member;
+    fn get_member(deps: Deps, addr: String) -> Option<StakerResponseItem> {
+        let raw = query(deps, mock_env(), QueryMsg::Staker { address: addr }).unwrap();
+        let res: StakerResponse = from_slice(&raw).unwrap();
+        res.member
     }
 
     // this tests the member queries
     fn assert_users(
         deps: Deps,
-        user1: Option<MemberResponseItem>,
-        user2: Option<MemberResponseItem>,
-        user3: Option<MemberResponseItem>,
+        user1: Option<StakerResponseItem>,
+        user2: Option<StakerResponseItem>,
+        user3: Option<StakerResponseItem>,
     ) {
         let member1 = get_member(deps, USER1.into());
         assert_eq!(member1, user1);
@@ -761,7 +1865,7 @@ mod tests {
                 let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
                     sender: addr.to_string(),
                     amount: Uint128::new(*stake),
-                    msg: to_binary(&ReceiveMsg::Bond {}).unwrap(),
+                    msg: to_binary(&ReceiveMsg::Bond { lock: None }).unwrap(),
                 });
                 let info = mock_info(STAKING_TOKEN_ADDRESS, &[]);
                 execute(deps.branch(), env.clone(), info, msg).unwrap();
@@ -769,6 +1873,15 @@ mod tests {
         }
     }
 
+    fn fund_rewards(deps: DepsMut, amount: u128) {
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: INIT_ADMIN.into(),
+            amount: Uint128::new(amount),
+            msg: to_binary(&ReceiveMsg::FundRewards {}).unwrap(),
+        });
+        execute(deps, mock_env(), mock_info(TERRALAND_TOKEN_ADDRESS, &[]), msg).unwrap();
+    }
+
     fn unbond(mut deps: DepsMut, user1: u128, user2: u128, user3: u128, height_delta: u64, funds: &[Coin]) {
         let env = get_env(height_delta);
 
@@ -787,27 +1900,31 @@ mod tests {
     fn assert_stake(deps: Deps, user1_stake: u128, user2_stake: u128, user3_stake: u128, height_delta: u64) {
         let env = get_env(height_delta);
 
-        let res1 = query_member(deps, env.clone(), USER1.into()).unwrap();
+        let res1 = query_staker(deps, env.clone(), USER1.into()).unwrap();
         assert_eq!(res1.member.unwrap().stake, user1_stake.into());
 
-        let res2 = query_member(deps, env.clone(), USER2.into()).unwrap();
+        let res2 = query_staker(deps, env.clone(), USER2.into()).unwrap();
         assert_eq!(res2.member.unwrap().stake, user2_stake.into());
 
-        let res3 = query_member(deps, env.clone(), USER3.into()).unwrap();
+        let res3 = query_staker(deps, env.clone(), USER3.into()).unwrap();
         assert_eq!(res3.member.unwrap().stake, user3_stake.into());
     }
 
+    fn first_reward(member: Option<StakerResponseItem>) -> Uint128 {
+        member.and_then(|m| m.rewards.first().map(|r| r.reward)).unwrap_or_default()
+    }
+
     fn assert_rewards(deps: Deps, user1_reward: u128, user2_reward: u128, user3_reward: u128, height_delta: u64) {
         let env = get_env(height_delta);
 
-        let res1 = query_member(deps, env.clone(), USER1.into()).unwrap();
-        assert_eq!(res1.member.unwrap_or(Default::default()).reward, user1_reward.into());
+        let res1 = query_staker(deps, env.clone(), USER1.into()).unwrap();
+        assert_eq!(first_reward(res1.member), user1_reward.into());
 
-        let res2 = query_member(deps, env.clone(), USER2.into()).unwrap();
-        assert_eq!(res2.member.unwrap_or(Default::default()).reward, user2_reward.into());
+        let res2 = query_staker(deps, env.clone(), USER2.into()).unwrap();
+        assert_eq!(first_reward(res2.member), user2_reward.into());
 
-        let res3 = query_member(deps, env.clone(), USER3.into()).unwrap();
-        assert_eq!(res3.member.unwrap_or(Default::default()).reward, user3_reward.into());
+        let res3 = query_staker(deps, env.clone(), USER3.into()).unwrap();
+        assert_eq!(first_reward(res3.member), user3_reward.into());
     }
 
     #[test]
@@ -903,4 +2020,1214 @@ mod tests {
                              &[Coin{ denom: "uusd".to_string(), amount: Uint128::new(1000000) }]);
         execute(deps.as_mut().branch(), env.clone(), info, msg).unwrap();
     }
+
+    #[test]
+    fn two_step_ownership() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+
+        let msg = ExecuteMsg::ProposeNewOwner { owner: USER1.into(), expiry: None };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(USER1, &[]), msg.clone())
+            .unwrap_err();
+        assert_eq!(err, ContractError::Ownership(OwnershipError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+
+        let raw = query(deps.as_ref(), mock_env(), QueryMsg::PendingOwner {}).unwrap();
+        let res: PendingOwnerResponse = from_slice(&raw).unwrap();
+        assert_eq!(res.owner, Some(USER1.into()));
+        assert_eq!(INIT_ADMIN, query_config(deps.as_ref()).unwrap().owner.as_str());
+
+        let msg = ExecuteMsg::AcceptOwnership {};
+        execute(deps.as_mut(), mock_env(), mock_info(USER1, &[]), msg).unwrap();
+        assert_eq!(USER1, query_config(deps.as_ref()).unwrap().owner.as_str());
+    }
+
+    #[test]
+    fn multiple_reward_streams() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+        let env = mock_env();
+
+        // existing streams cannot be replaced
+        let new_config = NewConfig {
+            staking_token: None,
+            unbonding_period: None,
+            burn_address: None,
+            instant_claim_percentage_loss: None,
+            reward_streams: Some(vec![RewardStream {
+                token: Denom::Native("uluna".to_string()),
+                distribution_schedule: vec![],
+            }]),
+            fee_config: None,
+            lock_tiers: None,
+            early_unlock: None,
+            treasury_address: None,
+            instant_claim_split: None,
+            instant_claim_curve: None,
+        };
+        let msg = ExecuteMsg::UpdateConfig(new_config.clone());
+        let err = execute(deps.as_mut(), env.clone(), mock_info(INIT_ADMIN, &[]), msg)
+            .unwrap_err();
+        assert_eq!(err, ContractError::InvalidRewardStreams {});
+
+        // add native stream, funded with uluna distributed over a week
+        fund_rewards(deps.as_mut(), 250_000_000_000);
+        let mut reward_streams = query_config(deps.as_ref()).unwrap().reward_streams;
+        reward_streams.push(RewardStream {
+            token: Denom::Native("uluna".to_string()),
+            distribution_schedule: vec![],
+        });
+        let msg = ExecuteMsg::UpdateConfig(NewConfig {
+            reward_streams: Some(reward_streams),
+            ..new_config
+        });
+        execute(deps.as_mut(), env.clone(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::AppendSchedule {
+            stream: 1,
+            schedule: Schedule {
+                amount: Uint128::new(604_800),
+                start_time: env.block.time.seconds(),
+                end_time: env.block.time.seconds() + WEEK,
+            },
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info(INIT_ADMIN, &[]), msg.clone())
+            .unwrap_err();
+        assert_eq!(err, ContractError::UnderfundedSchedule(1));
+
+        let funds = [Coin { denom: "uluna".to_string(), amount: Uint128::new(604_800) }];
+        execute(deps.as_mut(), env.clone(), mock_info(INIT_ADMIN, &funds), ExecuteMsg::FundRewards {})
+            .unwrap();
+        execute(deps.as_mut(), env, mock_info(INIT_ADMIN, &[]), msg).unwrap();
+
+        bond_cw20(deps.as_mut(), 10, 0, 0, 1);
+
+        // user is the only staker, so receives whole distribution of both streams
+        let member = query_staker(deps.as_ref(), get_env(2), USER1.into()).unwrap()
+            .member.unwrap();
+        assert_eq!(member.rewards.len(), 2);
+        assert_eq!(member.rewards[0].reward, Uint128::new(1_488_090));
+        assert_eq!(member.rewards[1].reward, Uint128::new(6));
+
+        let info = mock_info(USER1,
+                             &[Coin{ denom: "uusd".to_string(), amount: Uint128::new(1000000) }]);
+        let res = execute(deps.as_mut(), get_env(2), info.clone(), ExecuteMsg::Withdraw {})
+            .unwrap();
+        assert_eq!(res.messages, vec![
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: TERRALAND_TOKEN_ADDRESS.into(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: USER1.into(),
+                    amount: Uint128::new(1_488_090),
+                }).unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::new(BankMsg::Send {
+                to_address: USER1.into(),
+                amount: vec![Coin { denom: "uluna".to_string(), amount: Uint128::new(6) }],
+            }),
+        ]);
+
+        // everything was withdrawn
+        let err = execute(deps.as_mut(), get_env(2), info, ExecuteMsg::Withdraw {})
+            .unwrap_err();
+        assert_eq!(err, ContractError::NothingToWithdraw {});
+
+        let raw = query(deps.as_ref(), get_env(2), QueryMsg::RewardReserve {}).unwrap();
+        let res: RewardReserveResponse = from_slice(&raw).unwrap();
+        assert_eq!(res.streams[1], StreamReserveResponse {
+            token: Denom::Native("uluna".to_string()),
+            funded: Uint128::new(604_800),
+            scheduled: Uint128::new(604_800),
+            redistributed: Uint128::zero(),
+            paid: Uint128::new(6),
+            compounded: Uint128::zero(),
+            owed: Uint128::new(604_794),
+        });
+    }
+
+    #[test]
+    fn instantiate_with_funded_schedules() {
+        let mut deps = mock_dependencies(&[]);
+        let env = mock_env();
+        let schedule = Schedule {
+            amount: Uint128::new(1_000),
+            start_time: env.block.time.seconds(),
+            end_time: env.block.time.seconds() + WEEK,
+        };
+        let msg = InstantiateMsg {
+            owner: INIT_ADMIN.into(),
+            staking_token: Denom::Cw20(Addr::unchecked(STAKING_TOKEN_ADDRESS)),
+            terraland_token: TERRALAND_TOKEN_ADDRESS.into(),
+            unbonding_period: UNBONDING_PERIOD,
+            burn_address: BURN_ADDRESS.into(),
+            instant_claim_percentage_loss: 0,
+            reward_streams: vec![RewardStream {
+                token: Denom::Native("uluna".to_string()),
+                distribution_schedule: vec![schedule],
+            }],
+            fee_config: vec![],
+            lock_tiers: vec![],
+            early_unlock: EarlyUnlock::Forbidden {},
+            treasury_address: None,
+            instant_claim_split: PenaltySplit::default(),
+            instant_claim_curve: InstantClaimCurve::Linear {},
+        };
+
+        // schedules have to be funded with the instantiation
+        let funds = [Coin { denom: "uluna".to_string(), amount: Uint128::new(999) }];
+        let err = instantiate(deps.as_mut(), env.clone(), mock_info("creator", &funds), msg.clone())
+            .unwrap_err();
+        assert_eq!(err, ContractError::UnderfundedSchedule(0));
+        let funds = [Coin { denom: "uusd".to_string(), amount: Uint128::new(1_000) }];
+        let err = instantiate(deps.as_mut(), env.clone(), mock_info("creator", &funds), msg.clone())
+            .unwrap_err();
+        assert_eq!(err, ContractError::UnknownRewardToken("uusd".to_string()));
+
+        let funds = [Coin { denom: "uluna".to_string(), amount: Uint128::new(1_000) }];
+        instantiate(deps.as_mut(), env, mock_info("creator", &funds), msg).unwrap();
+        let reserve = query_reward_reserve(deps.as_ref()).unwrap();
+        assert_eq!(reserve.streams[0].funded, Uint128::new(1_000));
+        assert_eq!(reserve.streams[0].owed, Uint128::new(1_000));
+    }
+
+    #[test]
+    fn instantiate_with_cw20_reward_stream() {
+        let mut deps = mock_dependencies(&[]);
+        let env = mock_env();
+        let schedule = Schedule {
+            amount: Uint128::new(1_000),
+            start_time: env.block.time.seconds() + 10,
+            end_time: env.block.time.seconds() + WEEK,
+        };
+        let mut msg = InstantiateMsg {
+            owner: INIT_ADMIN.into(),
+            staking_token: Denom::Cw20(Addr::unchecked(STAKING_TOKEN_ADDRESS)),
+            terraland_token: TERRALAND_TOKEN_ADDRESS.into(),
+            unbonding_period: UNBONDING_PERIOD,
+            burn_address: BURN_ADDRESS.into(),
+            instant_claim_percentage_loss: 0,
+            reward_streams: vec![RewardStream {
+                token: Denom::Cw20(Addr::unchecked(TERRALAND_TOKEN_ADDRESS)),
+                distribution_schedule: vec![schedule.clone()],
+            }],
+            fee_config: vec![],
+            lock_tiers: vec![],
+            early_unlock: EarlyUnlock::Forbidden {},
+            treasury_address: None,
+            instant_claim_split: PenaltySplit::default(),
+            instant_claim_curve: InstantClaimCurve::Linear {},
+        };
+
+        // cw20 tokens cannot be sent along, so their schedules cannot be funded yet
+        let err = instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg.clone())
+            .unwrap_err();
+        assert_eq!(err, ContractError::Cw20ScheduleOnInstantiate(0));
+
+        // stream starts empty, tokens are deposited first and scheduled afterwards
+        msg.reward_streams[0].distribution_schedule = vec![];
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        let append = ExecuteMsg::AppendSchedule { stream: 0, schedule };
+        let err = execute(deps.as_mut(), env.clone(), mock_info(INIT_ADMIN, &[]), append.clone()).unwrap_err();
+        assert_eq!(err, ContractError::UnderfundedSchedule(0));
+        fund_rewards(deps.as_mut(), 1_000);
+        execute(deps.as_mut(), env, mock_info(INIT_ADMIN, &[]), append).unwrap();
+        let reserve = query_reward_reserve(deps.as_ref()).unwrap();
+        assert_eq!(reserve.streams[0].funded, Uint128::new(1_000));
+        assert_eq!(reserve.streams[0].scheduled, Uint128::new(1_000));
+    }
+
+    #[test]
+    fn fund_rewards_and_withdraw_surplus() {
+        let mut deps = mock_dependencies(&[Coin { denom: "uusd".to_string(), amount: Uint128::new(1_000) }]);
+        default_instantiate(deps.as_mut(), mock_env());
+        let env = mock_env();
+
+        // only tokens of the reward streams are accepted
+        let err = execute(deps.as_mut(), env.clone(), mock_info(INIT_ADMIN, &[]), ExecuteMsg::FundRewards {})
+            .unwrap_err();
+        assert_eq!(err, ContractError::Payment(PaymentError::NoFunds {}));
+        let funds = [Coin { denom: "uusd".to_string(), amount: Uint128::new(600) }];
+        let err = execute(deps.as_mut(), env.clone(), mock_info(INIT_ADMIN, &funds), ExecuteMsg::FundRewards {})
+            .unwrap_err();
+        assert_eq!(err, ContractError::UnknownRewardToken("uusd".to_string()));
+
+        let mut reward_streams = query_config(deps.as_ref()).unwrap().reward_streams;
+        reward_streams.push(RewardStream {
+            token: Denom::Native("uusd".to_string()),
+            distribution_schedule: vec![],
+        });
+        let msg = ExecuteMsg::UpdateConfig(NewConfig {
+            staking_token: None,
+            unbonding_period: None,
+            burn_address: None,
+            instant_claim_percentage_loss: None,
+            reward_streams: Some(reward_streams),
+            fee_config: None,
+            lock_tiers: None,
+            early_unlock: None,
+            treasury_address: None,
+            instant_claim_split: None,
+            instant_claim_curve: None,
+        });
+        execute(deps.as_mut(), env.clone(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+        let res = execute(deps.as_mut(), env.clone(), mock_info(INIT_ADMIN, &funds), ExecuteMsg::FundRewards {})
+            .unwrap();
+        assert_eq!(res.attributes[1], attr("tokens", "600 uusd"));
+
+        // schedule cannot exceed the funded amount
+        let append = |amount| ExecuteMsg::AppendSchedule {
+            stream: 1,
+            schedule: Schedule {
+                amount: Uint128::new(amount),
+                start_time: env.block.time.seconds(),
+                end_time: env.block.time.seconds() + WEEK,
+            },
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info(INIT_ADMIN, &[]), append(601))
+            .unwrap_err();
+        assert_eq!(err, ContractError::UnderfundedSchedule(1));
+        execute(deps.as_mut(), env.clone(), mock_info(INIT_ADMIN, &[]), append(500)).unwrap();
+
+        // owner withdraws only what is not owed to stakers
+        let withdraw = |amount| ExecuteMsg::UstWithdraw { recipient: INIT_ADMIN.into(), amount: Uint128::new(amount) };
+        let err = execute(deps.as_mut(), env.clone(), mock_info(INIT_ADMIN, &[]), withdraw(501))
+            .unwrap_err();
+        assert_eq!(err, ContractError::ReservedFunds {});
+        execute(deps.as_mut(), env.clone(), mock_info(INIT_ADMIN, &[]), withdraw(500)).unwrap();
+        let reserve = query_reward_reserve(deps.as_ref()).unwrap();
+        assert_eq!(reserve.streams[1], StreamReserveResponse {
+            token: Denom::Native("uusd".to_string()),
+            funded: Uint128::new(500),
+            scheduled: Uint128::new(500),
+            redistributed: Uint128::zero(),
+            paid: Uint128::zero(),
+            compounded: Uint128::zero(),
+            owed: Uint128::new(500),
+        });
+
+        // cw20 reward token balance above the owed amount can be withdrawn
+        let cfg = query_config(deps.as_ref()).unwrap();
+        let mut state = query_state(deps.as_ref()).unwrap();
+        let terraland_token = Denom::Cw20(Addr::unchecked(TERRALAND_TOKEN_ADDRESS));
+        let amount = release_unreserved(&cfg, &mut state, &terraland_token, Uint128::new(250_000_001_000))
+            .unwrap();
+        assert_eq!(amount, Uint128::new(1_000));
+
+        // staking token backs stakes and claims
+        let err = release_unreserved(&cfg, &mut state, &cfg.staking_token, Uint128::new(1_000))
+            .unwrap_err();
+        assert_eq!(err, ContractError::ReservedFunds {});
+    }
+
+    #[test]
+    fn staking_token_change() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+        let fee = [Coin{ denom: "uusd".to_string(), amount: Uint128::new(1000000) }];
+        let change = |token: &str| ExecuteMsg::UpdateConfig(NewConfig {
+            staking_token: Some(Denom::Cw20(Addr::unchecked(token))),
+            unbonding_period: None,
+            burn_address: None,
+            instant_claim_percentage_loss: None,
+            reward_streams: None,
+            fee_config: None,
+            lock_tiers: None,
+            early_unlock: None,
+            treasury_address: None,
+            instant_claim_split: None,
+            instant_claim_curve: None,
+        });
+
+        // staked tokens have to be paid back in the current token
+        bond_cw20(deps.as_mut(), 100, 0, 0, 1);
+        let err = execute(deps.as_mut(), get_env(2), mock_info(INIT_ADMIN, &[]), change(TERRALAND_TOKEN_ADDRESS))
+            .unwrap_err();
+        assert_eq!(err, ContractError::StakingTokenInUse {});
+        // setting the same token is still allowed
+        execute(deps.as_mut(), get_env(2), mock_info(INIT_ADMIN, &[]), change(STAKING_TOKEN_ADDRESS)).unwrap();
+
+        // so are claims of unbonded tokens
+        unbond(deps.as_mut(), 100, 0, 0, 2, &fee);
+        assert_eq!(query_state(deps.as_ref()).unwrap().total_stake, Uint128::zero());
+        let err = execute(deps.as_mut(), get_env(3), mock_info(INIT_ADMIN, &[]), change(TERRALAND_TOKEN_ADDRESS))
+            .unwrap_err();
+        assert_eq!(err, ContractError::StakingTokenInUse {});
+
+        // once everything is claimed the token can change
+        let mut env = get_env(3);
+        env.block.time = env.block.time.plus_seconds(UNBONDING_PERIOD);
+        execute(deps.as_mut(), env.clone(), mock_info(USER1, &fee), ExecuteMsg::Claim { amount: None }).unwrap();
+        execute(deps.as_mut(), env, mock_info(INIT_ADMIN, &[]), change(TERRALAND_TOKEN_ADDRESS)).unwrap();
+        let cfg = query_config(deps.as_ref()).unwrap();
+        assert_eq!(cfg.staking_token, Denom::Cw20(Addr::unchecked(TERRALAND_TOKEN_ADDRESS)));
+    }
+
+    #[test]
+    fn compound_reward() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+
+        // reward is paid in other token than staked one
+        let err = execute(deps.as_mut(), get_env(2), mock_info(USER1, &[]), ExecuteMsg::Compound {})
+            .unwrap_err();
+        assert_eq!(err, ContractError::CompoundNotSupported {});
+
+        let msg = ExecuteMsg::UpdateConfig(NewConfig {
+            staking_token: Some(Denom::Cw20(Addr::unchecked(TERRALAND_TOKEN_ADDRESS))),
+            unbonding_period: None,
+            burn_address: None,
+            instant_claim_percentage_loss: None,
+            reward_streams: None,
+            fee_config: None,
+            lock_tiers: None,
+            early_unlock: None,
+            treasury_address: None,
+            instant_claim_split: None,
+            instant_claim_curve: None,
+        });
+        execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: USER1.into(),
+            amount: Uint128::new(10),
+            msg: to_binary(&ReceiveMsg::Bond { lock: None }).unwrap(),
+        });
+        execute(deps.as_mut(), get_env(1), mock_info(TERRALAND_TOKEN_ADDRESS, &[]), msg).unwrap();
+
+        execute(deps.as_mut(), get_env(2), mock_info(USER1, &[]), ExecuteMsg::Compound {})
+            .unwrap();
+        let member = query_staker(deps.as_ref(), get_env(2), USER1.into()).unwrap().member.unwrap();
+        assert_eq!(member.stake, Uint128::new(1_488_100));
+        assert_eq!(member.rewards[0].withdrawn, Uint128::new(1_488_090));
+        assert_eq!(query_state(deps.as_ref()).unwrap().total_stake, Uint128::new(1_488_100));
+
+        // nothing left to compound in the same block
+        let err = execute(deps.as_mut(), get_env(2), mock_info(USER1, &[]), ExecuteMsg::Compound {})
+            .unwrap_err();
+        assert_eq!(err, ContractError::NothingToWithdraw {});
+
+        // reward is compounded on unbond
+        let msg = ExecuteMsg::SetAutoCompound { enabled: true };
+        execute(deps.as_mut(), get_env(2), mock_info(USER1, &[]), msg).unwrap();
+        unbond(deps.as_mut(), 100, 0, 0, 3,
+               &[Coin{ denom: "uusd".to_string(), amount: Uint128::new(1000000) }]);
+
+        let member = query_staker(deps.as_ref(), get_env(3), USER1.into()).unwrap().member.unwrap();
+        assert!(member.auto_compound);
+        assert_eq!(member.stake, Uint128::new(2_976_089));
+        assert_eq!(query_state(deps.as_ref()).unwrap().total_stake, Uint128::new(2_976_089));
+
+        // compounded reward is no longer owed, but it is not paid out either
+        let reserve = query_reward_reserve(deps.as_ref()).unwrap();
+        assert_eq!(reserve.streams[0].paid, Uint128::zero());
+        assert_eq!(reserve.streams[0].compounded, Uint128::new(2_976_179));
+        assert_eq!(reserve.streams[0].owed, Uint128::new(250_000_000_000 - 2_976_179));
+    }
+
+    #[test]
+    fn lock_tiers() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+        let fee = [Coin{ denom: "uusd".to_string(), amount: Uint128::new(1000000) }];
+
+        // only configured lock periods are accepted
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: USER1.into(),
+            amount: Uint128::new(10),
+            msg: to_binary(&ReceiveMsg::Bond { lock: Some(1) }).unwrap(),
+        });
+        let err = execute(deps.as_mut(), get_env(1), mock_info(STAKING_TOKEN_ADDRESS, &[]), msg)
+            .unwrap_err();
+        assert_eq!(err, ContractError::LockTierNotFound(1));
+
+        // locked tokens count twice
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: USER1.into(),
+            amount: Uint128::new(10),
+            msg: to_binary(&ReceiveMsg::Bond { lock: Some(WEEK) }).unwrap(),
+        });
+        execute(deps.as_mut(), get_env(1), mock_info(STAKING_TOKEN_ADDRESS, &[]), msg).unwrap();
+        bond_cw20(deps.as_mut(), 0, 10, 0, 1);
+        assert_eq!(query_state(deps.as_ref()).unwrap().total_stake, Uint128::new(30));
+        assert_rewards(deps.as_ref(), 992_060, 496_030, 0, 2);
+
+        // early unlock is forbidden
+        let msg = ExecuteMsg::Unbond { tokens: Uint128::new(1) };
+        let err = execute(deps.as_mut(), get_env(2), mock_info(USER1, &fee), msg.clone())
+            .unwrap_err();
+        assert_eq!(err, ContractError::StakeLocked {});
+
+        // or penalized
+        let msg = ExecuteMsg::UpdateConfig(NewConfig {
+            staking_token: None,
+            unbonding_period: None,
+            burn_address: None,
+            instant_claim_percentage_loss: None,
+            reward_streams: None,
+            fee_config: None,
+            lock_tiers: None,
+            early_unlock: Some(EarlyUnlock::Penalty { percentage: 50 }),
+            treasury_address: None,
+            instant_claim_split: None,
+            instant_claim_curve: None,
+        });
+        execute(deps.as_mut(), get_env(2), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+        let msg = ExecuteMsg::Unbond { tokens: Uint128::new(4) };
+        let res = execute(deps.as_mut(), get_env(2), mock_info(USER1, &fee), msg).unwrap();
+        assert_eq!(res.messages, vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: STAKING_TOKEN_ADDRESS.into(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: BURN_ADDRESS.into(),
+                amount: Uint128::new(2),
+            }).unwrap(),
+            funds: vec![],
+        })]);
+        let member = query_staker(deps.as_ref(), get_env(2), USER1.into()).unwrap()
+            .member.unwrap();
+        assert_eq!(member.stake, Uint128::new(6));
+        assert_eq!(member.locks[0].amount, Uint128::new(6));
+        assert_eq!(member.claims[0].amount, Uint128::new(2));
+        assert_eq!(query_state(deps.as_ref()).unwrap().total_stake, Uint128::new(22));
+
+        // boost ends with the lock
+        bond_cw20(deps.as_mut(), 1, 0, 0, 100802);
+        let member = query_staker(deps.as_ref(), get_env(100802), USER1.into()).unwrap()
+            .member.unwrap();
+        assert!(member.locks.is_empty());
+        assert_eq!(query_state(deps.as_ref()).unwrap().total_stake, Uint128::new(17));
+    }
+
+    #[test]
+    fn lock_boost_ends_at_unlock_time() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: USER1.into(),
+            amount: Uint128::new(100),
+            msg: to_binary(&ReceiveMsg::Bond { lock: Some(WEEK) }).unwrap(),
+        });
+        execute(deps.as_mut(), get_env(1), mock_info(STAKING_TOKEN_ADDRESS, &[]), msg).unwrap();
+        bond_cw20(deps.as_mut(), 0, 100, 0, 1);
+
+        // locked stake earns twice as much until the lock ends
+        let unlock = 1 + WEEK / 6;
+        let user1 = first_reward(query_staker(deps.as_ref(), get_env(unlock), USER1.into()).unwrap().member);
+        let user2 = first_reward(query_staker(deps.as_ref(), get_env(unlock), USER2.into()).unwrap().member);
+        assert_eq!(user1, user2 + user2);
+
+        // afterwards both earn the same, nobody had to interact with the contract
+        let later = unlock + 100;
+        let user1_later = first_reward(query_staker(deps.as_ref(), get_env(later), USER1.into()).unwrap().member);
+        let user2_later = first_reward(query_staker(deps.as_ref(), get_env(later), USER2.into()).unwrap().member);
+        assert!(user2_later > user2);
+        assert_eq!(user1_later - user1, user2_later - user2);
+        assert_eq!(query_total_weight(deps.as_ref(), get_env(later)).unwrap().weight, 200);
+        let member = query_member(deps.as_ref(), get_env(later), USER1.into(), None).unwrap();
+        assert_eq!(member.weight, Some(100));
+
+        // expiry is settled into the state by the next interaction of any member
+        bond_cw20(deps.as_mut(), 0, 10, 0, later);
+        assert_eq!(query_state(deps.as_ref()).unwrap().total_stake, Uint128::new(210));
+        let user1_settled = first_reward(query_staker(deps.as_ref(), get_env(later), USER1.into()).unwrap().member);
+        assert_eq!(user1_settled, user1_later);
+    }
+
+    #[test]
+    fn lock_expiry_snapshots_and_hooks() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+        let hook = "hook1234567890";
+        let msg = ExecuteMsg::AddHook { addr: hook.into() };
+        execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: USER1.into(),
+            amount: Uint128::new(100),
+            msg: to_binary(&ReceiveMsg::Bond { lock: Some(WEEK) }).unwrap(),
+        });
+        execute(deps.as_mut(), get_env(1), mock_info(STAKING_TOKEN_ADDRESS, &[]), msg).unwrap();
+        bond_cw20(deps.as_mut(), 0, 100, 0, 1);
+
+        // next interaction after the unlock time releases the boost of the lock holder
+        let later = 1 + WEEK / 6 + 100;
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: USER2.into(),
+            amount: Uint128::new(10),
+            msg: to_binary(&ReceiveMsg::Bond { lock: None }).unwrap(),
+        });
+        let res = execute(deps.as_mut(), get_env(later), mock_info(STAKING_TOKEN_ADDRESS, &[]), msg).unwrap();
+        let diffs = vec![
+            MemberDiff::new(USER1, Some(200), Some(100)),
+            MemberDiff::new(USER2, Some(100), Some(110)),
+        ];
+        assert_eq!(res.messages, vec![
+            SubMsg::new(MemberChangedHookMsg::new(diffs).into_cosmos_msg(hook).unwrap()),
+        ]);
+
+        // weights at heights follow the release
+        let height = get_env(later).block.height;
+        let total = query_total_stake_at_height(deps.as_ref(), height).unwrap().total_stake;
+        assert_eq!(total, Uint128::new(300));
+        let total = query_total_stake_at_height(deps.as_ref(), height + 1).unwrap().total_stake;
+        assert_eq!(total, Uint128::new(210));
+        let member = query_member(deps.as_ref(), get_env(later), USER1.into(), Some(height)).unwrap();
+        assert_eq!(member.weight, Some(200));
+        let member = query_member(deps.as_ref(), get_env(later), USER1.into(), Some(height + 1)).unwrap();
+        assert_eq!(member.weight, Some(100));
+        let member = query_member(deps.as_ref(), get_env(later), USER1.into(), None).unwrap();
+        assert_eq!(member.weight, Some(100));
+
+        // released member keeps the rewards earned with the boost
+        let user1 = first_reward(query_staker(deps.as_ref(), get_env(later), USER1.into()).unwrap().member);
+        let user2 = query_staker(deps.as_ref(), get_env(later), USER2.into()).unwrap().member.unwrap();
+        assert!(user1 > user2.rewards[0].reward);
+        assert!(query_staker(deps.as_ref(), get_env(later), USER1.into()).unwrap().member.unwrap().locks.is_empty());
+    }
+
+    #[test]
+    fn rebond_claims() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+        let fee = [Coin{ denom: "uusd".to_string(), amount: Uint128::new(1000000) }];
+
+        bond_cw20(deps.as_mut(), 12_000, 0, 0, 1);
+        unbond(deps.as_mut(), 100, 0, 0, 2, &fee);
+        unbond(deps.as_mut(), 200, 0, 0, 3, &fee);
+
+        // newest claim is consumed first
+        let msg = ExecuteMsg::Rebond { amount: Some(Uint128::new(250)) };
+        execute(deps.as_mut(), get_env(4), mock_info(USER1, &[]), msg).unwrap();
+        let member = query_staker(deps.as_ref(), get_env(4), USER1.into()).unwrap()
+            .member.unwrap();
+        assert_eq!(member.stake, Uint128::new(11_950));
+        assert_eq!(member.claims, vec![Claim {
+            amount: Uint128::new(50),
+            release_at: Expiration::AtTime(get_env(2).block.time.plus_seconds(UNBONDING_PERIOD)),
+        }]);
+
+        let msg = ExecuteMsg::Rebond { amount: Some(Uint128::new(100)) };
+        let err = execute(deps.as_mut(), get_env(4), mock_info(USER1, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::NotEnoughClaims {});
+
+        // rest of the claims
+        let msg = ExecuteMsg::Rebond { amount: None };
+        execute(deps.as_mut(), get_env(4), mock_info(USER1, &[]), msg.clone()).unwrap();
+        let member = query_staker(deps.as_ref(), get_env(4), USER1.into()).unwrap()
+            .member.unwrap();
+        assert_eq!(member.stake, Uint128::new(12_000));
+        assert!(member.claims.is_empty());
+        assert_eq!(query_state(deps.as_ref()).unwrap().total_stake, Uint128::new(12_000));
+
+        let err = execute(deps.as_mut(), get_env(4), mock_info(USER1, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::NothingToClaim {});
+    }
+
+    #[test]
+    fn reward_projection_queries() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+        bond_cw20(deps.as_mut(), 12_000, 0, 0, 1);
+
+        let raw = query(deps.as_ref(), get_env(2), QueryMsg::Apr {}).unwrap();
+        let res: AprResponse = from_slice(&raw).unwrap();
+        assert_eq!(res.streams[0].apr, Decimal::from_ratio(651_783_420u128, 1u128));
+
+        let msg = QueryMsg::SimulateReward {
+            address: USER1.into(),
+            at_time: get_env(3).block.time.seconds(),
+        };
+        let raw = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let res: SimulateRewardResponse = from_slice(&raw).unwrap();
+        let member = query_staker(deps.as_ref(), get_env(3), USER1.into()).unwrap()
+            .member.unwrap();
+        assert_eq!(res.rewards, member.rewards);
+        assert_eq!(res.rewards[0].reward, Uint128::new(2_976_180));
+
+        // past cannot be simulated
+        let msg = QueryMsg::SimulateReward {
+            address: USER1.into(),
+            at_time: mock_env().block.time.seconds(),
+        };
+        query(deps.as_ref(), mock_env(), msg).unwrap_err();
+
+        let raw = query(deps.as_ref(), get_env(2), QueryMsg::DistributionStatus {}).unwrap();
+        let res: DistributionStatusResponse = from_slice(&raw).unwrap();
+        let schedules = &res.streams[0].schedules;
+        assert_eq!(schedules[0].amount_per_sec, Uint128::new(248_015));
+        assert_eq!(schedules[0].distributed, Uint128::new(2_976_180));
+        assert_eq!(schedules[0].remaining, Uint128::new(149_997_023_820));
+        assert_eq!(schedules[1].distributed, Uint128::zero());
+        assert_eq!(schedules[1].remaining, Uint128::new(100_000_000_000));
+    }
+
+    #[test]
+    fn append_schedule() {
+        let mut deps = mock_dependencies(&[]);
+        let env = mock_env();
+        default_instantiate(deps.as_mut(), env.clone());
+        fund_rewards(deps.as_mut(), 250_000_000_100);
+        bond_cw20(deps.as_mut(), 12_000, 0, 0, 1);
+
+        let start_time = env.block.time.seconds() + 2 * WEEK;
+        let append = |stream, start_time, end_time| ExecuteMsg::AppendSchedule {
+            stream,
+            schedule: Schedule { amount: Uint128::new(100), start_time, end_time },
+        };
+
+        let err = execute(deps.as_mut(), get_env(2), mock_info(USER1, &[]),
+                          append(0, start_time, start_time + WEEK)).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let err = execute(deps.as_mut(), get_env(2), mock_info(INIT_ADMIN, &[]),
+                          append(1, start_time, start_time + WEEK)).unwrap_err();
+        assert_eq!(err, ContractError::RewardStreamNotFound(1));
+        let err = execute(deps.as_mut(), get_env(2), mock_info(INIT_ADMIN, &[]),
+                          append(0, start_time, start_time)).unwrap_err();
+        assert_eq!(err, ContractError::InvalidSchedulePeriod {});
+        let err = execute(deps.as_mut(), get_env(2), mock_info(INIT_ADMIN, &[]),
+                          append(0, start_time - 1, start_time + WEEK)).unwrap_err();
+        assert_eq!(err, ContractError::OverlappingSchedules {});
+
+        execute(deps.as_mut(), get_env(2), mock_info(INIT_ADMIN, &[]),
+                append(0, start_time, start_time + WEEK)).unwrap();
+        let schedules = &query_config(deps.as_ref()).unwrap().reward_streams[0].distribution_schedule;
+        assert_eq!(schedules.len(), 3);
+
+        // global index was checkpointed and rewards are not affected
+        let state = query_state(deps.as_ref()).unwrap();
+        assert_eq!(state.last_updated, get_env(2).block.time.seconds());
+        assert_rewards(deps.as_ref(), 2_976_180, 0, 0, 3);
+
+        // started schedule cannot be rewritten
+        let mut reward_streams = query_config(deps.as_ref()).unwrap().reward_streams;
+        reward_streams[0].distribution_schedule[0].amount = Uint128::new(1);
+        let msg = ExecuteMsg::UpdateConfig(NewConfig {
+            staking_token: None,
+            unbonding_period: None,
+            burn_address: None,
+            instant_claim_percentage_loss: None,
+            reward_streams: Some(reward_streams),
+            fee_config: None,
+            lock_tiers: None,
+            early_unlock: None,
+            treasury_address: None,
+            instant_claim_split: None,
+            instant_claim_curve: None,
+        });
+        let err = execute(deps.as_mut(), get_env(2), mock_info(INIT_ADMIN, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::ScheduleAlreadyStarted {});
+    }
+
+    #[test]
+    fn rewards_across_schedule_gap() {
+        let mut deps = mock_dependencies(&[]);
+        let env = mock_env();
+        default_instantiate(deps.as_mut(), env.clone());
+        fund_rewards(deps.as_mut(), 350_000_000_000);
+        bond_cw20(deps.as_mut(), 100, 0, 0, 1);
+
+        // next schedule starts a week after the current ones end
+        let start_time = env.block.time.seconds() + 3 * WEEK;
+        let msg = ExecuteMsg::AppendSchedule {
+            stream: 0,
+            schedule: Schedule {
+                amount: Uint128::new(100_000_000_000),
+                start_time,
+                end_time: start_time + WEEK,
+            },
+        };
+        execute(deps.as_mut(), get_env(2), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+
+        // update inside the gap keeps rewards of the finished schedules
+        let gap = (2 * WEEK + 600) / 6;
+        let week = WEEK as u128;
+        let distributed = 248_015 * (week - 6) + 165_343 * week;
+        assert_rewards(deps.as_ref(), distributed, 0, 0, gap);
+        bond_cw20(deps.as_mut(), 0, 100, 0, gap);
+        assert_rewards(deps.as_ref(), distributed, 0, 0, gap + 100);
+
+        // schedule after the gap is shared by both stakers
+        let after = (4 * WEEK + 600) / 6;
+        assert_rewards(deps.as_ref(), distributed + 165_343 * week / 2, 165_343 * week / 2, 0, after);
+    }
+
+    #[test]
+    fn bond_for_beneficiary() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: USER1.into(),
+            amount: Uint128::new(500),
+            msg: to_binary(&ReceiveMsg::BondFor { beneficiary: USER2.into() }).unwrap(),
+        });
+        let res = execute(deps.as_mut(), get_env(1), mock_info(STAKING_TOKEN_ADDRESS, &[]), msg)
+            .unwrap();
+        assert_eq!(res.attributes[3], attr("sender", USER1));
+        assert_eq!(res.attributes[4], attr("beneficiary", USER2));
+
+        assert_eq!(query_staker(deps.as_ref(), get_env(1), USER1.into()).unwrap().member, None);
+        let member = query_staker(deps.as_ref(), get_env(1), USER2.into()).unwrap().member;
+        assert_eq!(member.unwrap().stake, Uint128::new(500));
+    }
+
+    #[test]
+    fn native_token_bond() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+        let fee = [Coin{ denom: "uusd".to_string(), amount: Uint128::new(1000000) }];
+
+        let msg = ExecuteMsg::UpdateConfig(NewConfig {
+            staking_token: Some(Denom::Native("ustake".to_string())),
+            unbonding_period: None,
+            burn_address: None,
+            instant_claim_percentage_loss: None,
+            reward_streams: None,
+            fee_config: None,
+            lock_tiers: None,
+            early_unlock: None,
+            treasury_address: None,
+            instant_claim_split: None,
+            instant_claim_curve: None,
+        });
+        execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+
+        // cw20 tokens and other denoms are rejected
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: USER1.into(),
+            amount: Uint128::new(100),
+            msg: to_binary(&ReceiveMsg::Bond { lock: None }).unwrap(),
+        });
+        let err = execute(deps.as_mut(), get_env(1), mock_info(STAKING_TOKEN_ADDRESS, &[]), msg)
+            .unwrap_err();
+        assert_eq!(err, ContractError::MissedToken {});
+        let err = execute(deps.as_mut(), get_env(1), mock_info(USER1, &fee),
+                          ExecuteMsg::Bond { lock: None }).unwrap_err();
+        assert_eq!(err, ContractError::Payment(PaymentError::MissingDenom("ustake".to_string())));
+
+        let funds = [Coin { denom: "ustake".to_string(), amount: Uint128::new(100) }];
+        execute(deps.as_mut(), get_env(1), mock_info(USER1, &funds), ExecuteMsg::Bond { lock: None })
+            .unwrap();
+        unbond(deps.as_mut(), 100, 0, 0, 2, &fee);
+
+        // unbonded coins are sent back
+        let env = get_env(2 + UNBONDING_PERIOD / 6);
+        let res = execute(deps.as_mut(), env, mock_info(USER1, &fee), ExecuteMsg::Claim { amount: None }).unwrap();
+        assert_eq!(res.messages, vec![SubMsg::new(BankMsg::Send {
+            to_address: USER1.into(),
+            amount: funds.to_vec(),
+        })]);
+    }
+
+    #[test]
+    fn cw4_members_and_hooks() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+        let fee = [Coin{ denom: "uusd".to_string(), amount: Uint128::new(1000000) }];
+        let hook = "hook1234567890";
+
+        // only owner manages hooks
+        let msg = ExecuteMsg::AddHook { addr: hook.into() };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(USER1, &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg.clone()).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::Hook(HookError::HookAlreadyRegistered {}));
+        let hooks: HooksResponse = from_slice(&query(deps.as_ref(), mock_env(), QueryMsg::Hooks {}).unwrap())
+            .unwrap();
+        assert_eq!(hooks.hooks, vec![hook.to_string()]);
+
+        // bond notifies about the new member
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: USER1.into(),
+            amount: Uint128::new(500),
+            msg: to_binary(&ReceiveMsg::Bond { lock: None }).unwrap(),
+        });
+        let res = execute(deps.as_mut(), get_env(1), mock_info(STAKING_TOKEN_ADDRESS, &[]), msg).unwrap();
+        let diff = MemberDiff::new(USER1, None, Some(500));
+        assert_eq!(res.messages, vec![
+            SubMsg::new(MemberChangedHookMsg::one(diff).into_cosmos_msg(hook).unwrap()),
+        ]);
+        bond_cw20(deps.as_mut(), 0, 300, 0, 1);
+
+        // unbond notifies about the weight change
+        let msg = ExecuteMsg::Unbond { tokens: Uint128::new(200) };
+        let res = execute(deps.as_mut(), get_env(2), mock_info(USER1, &fee), msg).unwrap();
+        let diff = MemberDiff::new(USER1, Some(500), Some(300));
+        assert_eq!(res.messages, vec![
+            SubMsg::new(MemberChangedHookMsg::one(diff).into_cosmos_msg(hook).unwrap()),
+        ]);
+
+        let member = query_member(deps.as_ref(), get_env(2), USER1.into(), None).unwrap();
+        assert_eq!(member.weight, Some(300));
+        let member = query_member(deps.as_ref(), get_env(2), USER3.into(), None).unwrap();
+        assert_eq!(member.weight, None);
+        assert_eq!(query_total_weight(deps.as_ref(), get_env(2)).unwrap().weight, 600);
+        let members = query_member_list(deps.as_ref(), get_env(2), None, None).unwrap().members;
+        assert_eq!(members, vec![
+            Member { addr: USER2.into(), weight: 300 },
+            Member { addr: USER1.into(), weight: 300 },
+        ]);
+
+        // removed hook is no longer notified
+        let msg = ExecuteMsg::RemoveHook { addr: hook.into() };
+        execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+        let msg = ExecuteMsg::Unbond { tokens: Uint128::new(100) };
+        let res = execute(deps.as_mut(), get_env(3), mock_info(USER1, &fee), msg).unwrap();
+        assert!(res.messages.is_empty());
+        // weight above u64 is reported saturated and does not block bonding
+        bond_cw20(deps.as_mut(), 0, 0, u64::MAX as u128, 3);
+        let member = query_member(deps.as_ref(), get_env(3), USER3.into(), None).unwrap();
+        assert_eq!(member.weight, Some(u64::MAX));
+        assert_eq!(query_total_weight(deps.as_ref(), get_env(3)).unwrap().weight, u64::MAX);
+    }
+
+    #[test]
+    fn cw4_queries() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+        bond_cw20(deps.as_mut(), 500, 300, 0, 1);
+
+        // raw cw4 json is served with cw4 responses
+        let msg = from_slice(br#"{"member":{"addr":"somebody","at_height":null}}"#).unwrap();
+        let res: MemberResponse = from_slice(&query(deps.as_ref(), get_env(2), msg).unwrap()).unwrap();
+        assert_eq!(res.weight, Some(500));
+        let msg = from_slice(br#"{"member":{"addr":"funny"}}"#).unwrap();
+        let res: MemberResponse = from_slice(&query(deps.as_ref(), get_env(2), msg).unwrap()).unwrap();
+        assert_eq!(res.weight, None);
+        let msg = from_slice(br#"{"list_members":{"start_after":null,"limit":null}}"#).unwrap();
+        let res: MemberListResponse = from_slice(&query(deps.as_ref(), get_env(2), msg).unwrap()).unwrap();
+        assert_eq!(res.members, vec![
+            Member { addr: USER2.into(), weight: 300 },
+            Member { addr: USER1.into(), weight: 500 },
+        ]);
+
+        // cw4 query messages are understood as they are
+        let msg = to_binary(&Cw4QueryMsg::Member { addr: USER1.into(), at_height: Some(mock_env().block.height + 2) })
+            .unwrap();
+        let res: MemberResponse = from_slice(&query(deps.as_ref(), get_env(2), from_slice(&msg).unwrap()).unwrap())
+            .unwrap();
+        assert_eq!(res.weight, Some(500));
+        let msg = to_binary(&Cw4QueryMsg::TotalWeight {}).unwrap();
+        let res: TotalWeightResponse = from_slice(&query(deps.as_ref(), get_env(2), from_slice(&msg).unwrap()).unwrap())
+            .unwrap();
+        assert_eq!(res.weight, 800);
+    }
+
+    #[test]
+    fn stake_at_height() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+        let fee = [Coin{ denom: "uusd".to_string(), amount: Uint128::new(1000000) }];
+        let height = mock_env().block.height;
+
+        bond_cw20(deps.as_mut(), 500, 0, 0, 1);
+        bond_cw20(deps.as_mut(), 0, 300, 0, 2);
+        unbond(deps.as_mut(), 200, 0, 0, 5, &fee);
+
+        // snapshot holds the value from the beginning of the block
+        for (delta, user1, user2, total) in &[(1, 0, 0, 0), (2, 500, 0, 500), (5, 500, 300, 800), (6, 300, 300, 600)] {
+            let res = query_stake_at_height(deps.as_ref(), USER1.into(), height + delta).unwrap();
+            assert_eq!(res.stake, Uint128::new(*user1));
+            let res = query_stake_at_height(deps.as_ref(), USER2.into(), height + delta).unwrap();
+            assert_eq!(res.stake, Uint128::new(*user2));
+            let res = query_total_stake_at_height(deps.as_ref(), height + delta).unwrap();
+            assert_eq!(res.total_stake, Uint128::new(*total));
+        }
+
+        // cw4 member query reads the same snapshots
+        let member = query_member(deps.as_ref(), get_env(2), USER1.into(), Some(height + 2)).unwrap();
+        assert_eq!(member.weight, Some(500));
+        let member = query_member(deps.as_ref(), get_env(2), USER2.into(), Some(height + 2)).unwrap();
+        assert_eq!(member.weight, None);
+    }
+
+    #[test]
+    fn partial_claims() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+        let fee = [Coin{ denom: "uusd".to_string(), amount: Uint128::new(1000000) }];
+
+        let msg = ExecuteMsg::UpdateConfig(NewConfig {
+            staking_token: None,
+            unbonding_period: None,
+            burn_address: None,
+            instant_claim_percentage_loss: Some(10),
+            reward_streams: None,
+            fee_config: None,
+            lock_tiers: None,
+            early_unlock: None,
+            treasury_address: None,
+            instant_claim_split: None,
+            instant_claim_curve: None,
+        });
+        execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+
+        bond_cw20(deps.as_mut(), 1_000, 0, 0, 1);
+        unbond(deps.as_mut(), 300, 0, 0, 2, &fee);
+        unbond(deps.as_mut(), 200, 0, 0, 3, &fee);
+        let first_release = Expiration::AtTime(get_env(2).block.time.plus_seconds(UNBONDING_PERIOD));
+        let second_release = Expiration::AtTime(get_env(3).block.time.plus_seconds(UNBONDING_PERIOD));
+
+        // penalty is charged on the released slice only, oldest claims first
+        let msg = ExecuteMsg::InstantClaim { amount: Some(Uint128::new(250)) };
+        let res = execute(deps.as_mut(), get_env(4), mock_info(USER1, &fee), msg).unwrap();
+        assert_eq!(res.attributes[1], attr("tokens", format!("226 {}", STAKING_TOKEN_ADDRESS)));
+        assert_eq!(res.attributes[2], attr("fee", format!("24 {}", STAKING_TOKEN_ADDRESS)));
+        let member = query_staker(deps.as_ref(), get_env(4), USER1.into()).unwrap().member.unwrap();
+        assert_eq!(member.claims, vec![
+            Claim { amount: Uint128::new(50), release_at: first_release },
+            Claim { amount: Uint128::new(200), release_at: second_release },
+        ]);
+
+        // only the first claim is mature
+        let env = get_env(2 + UNBONDING_PERIOD / 6);
+        let msg = ExecuteMsg::Claim { amount: Some(Uint128::new(100)) };
+        let err = execute(deps.as_mut(), env.clone(), mock_info(USER1, &fee), msg).unwrap_err();
+        assert_eq!(err, ContractError::NotEnoughClaims {});
+        let msg = ExecuteMsg::Claim { amount: Some(Uint128::zero()) };
+        let err = execute(deps.as_mut(), env.clone(), mock_info(USER1, &fee), msg).unwrap_err();
+        assert_eq!(err, ContractError::NothingToClaim {});
+
+        let msg = ExecuteMsg::Claim { amount: Some(Uint128::new(30)) };
+        let res = execute(deps.as_mut(), env.clone(), mock_info(USER1, &fee), msg).unwrap();
+        assert_eq!(res.messages, vec![transfer_msg(
+            &Denom::Cw20(Addr::unchecked(STAKING_TOKEN_ADDRESS)),
+            &Addr::unchecked(USER1),
+            Uint128::new(30),
+        ).unwrap()]);
+        let member = query_staker(deps.as_ref(), env, USER1.into()).unwrap().member.unwrap();
+        assert_eq!(member.claims, vec![
+            Claim { amount: Uint128::new(20), release_at: first_release },
+            Claim { amount: Uint128::new(200), release_at: second_release },
+        ]);
+    }
+
+    #[test]
+    fn sliding_instant_claim_fee() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+        let fee = [Coin{ denom: "uusd".to_string(), amount: Uint128::new(1000000) }];
+
+        let msg = ExecuteMsg::UpdateConfig(NewConfig {
+            staking_token: None,
+            unbonding_period: None,
+            burn_address: None,
+            instant_claim_percentage_loss: Some(10),
+            reward_streams: None,
+            fee_config: None,
+            lock_tiers: None,
+            early_unlock: None,
+            treasury_address: None,
+            instant_claim_split: None,
+            instant_claim_curve: None,
+        });
+        execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+
+        bond_cw20(deps.as_mut(), 1_000, 0, 0, 1);
+        unbond(deps.as_mut(), 600, 0, 0, 2, &fee);
+        unbond(deps.as_mut(), 400, 0, 0, 52, &fee);
+
+        // half of the unbonding period left for the first claim, whole one for the second
+        let msg = QueryMsg::SimulateInstantClaim { address: USER1.into(), amount: None };
+        let raw = query(deps.as_ref(), get_env(52), msg.clone()).unwrap();
+        let res: SimulateInstantClaimResponse = from_slice(&raw).unwrap();
+        assert_eq!(res, SimulateInstantClaimResponse {
+            amount: Uint128::new(930),
+            fee: Uint128::new(70),
+            burned: Uint128::new(70),
+            treasury: Uint128::zero(),
+            redistributed: Uint128::zero(),
+        });
+
+        // simulation releases the same claims as the instant claim of the amount
+        let msg_amount = QueryMsg::SimulateInstantClaim { address: USER1.into(), amount: Some(Uint128::new(700)) };
+        let raw = query(deps.as_ref(), get_env(52), msg_amount).unwrap();
+        let res: SimulateInstantClaimResponse = from_slice(&raw).unwrap();
+        assert_eq!((res.amount, res.fee), (Uint128::new(660), Uint128::new(40)));
+        let msg_claim = ExecuteMsg::InstantClaim { amount: Some(Uint128::new(700)) };
+        let res = execute(deps.as_mut(), get_env(52), mock_info(USER1, &fee), msg_claim).unwrap();
+        let staking_token = Denom::Cw20(Addr::unchecked(STAKING_TOKEN_ADDRESS));
+        assert_eq!(res.messages, vec![
+            transfer_msg(&staking_token, &Addr::unchecked(USER1), Uint128::new(660)).unwrap(),
+            transfer_msg(&staking_token, &Addr::unchecked(BURN_ADDRESS), Uint128::new(40)).unwrap(),
+        ]);
+
+        let raw = query(deps.as_ref(), get_env(52), msg.clone()).unwrap();
+        let res: SimulateInstantClaimResponse = from_slice(&raw).unwrap();
+        assert_eq!((res.amount, res.fee), (Uint128::new(270), Uint128::new(30)));
+        let msg_claim = ExecuteMsg::InstantClaim { amount: None };
+        execute(deps.as_mut(), get_env(52), mock_info(USER1, &fee), msg_claim.clone()).unwrap();
+        let raw = query(deps.as_ref(), get_env(52), msg.clone()).unwrap();
+        let res: SimulateInstantClaimResponse = from_slice(&raw).unwrap();
+        assert_eq!(res, SimulateInstantClaimResponse::default());
+
+        // claims maturing after a year are neither released nor simulated
+        let msg_config = ExecuteMsg::UpdateConfig(NewConfig {
+            staking_token: None,
+            unbonding_period: Some(YEAR_IN_SEC + 600),
+            burn_address: None,
+            instant_claim_percentage_loss: None,
+            reward_streams: None,
+            fee_config: None,
+            lock_tiers: None,
+            early_unlock: None,
+            treasury_address: None,
+            instant_claim_split: None,
+            instant_claim_curve: None,
+        });
+        execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg_config).unwrap();
+        bond_cw20(deps.as_mut(), 100, 0, 0, 53);
+        unbond(deps.as_mut(), 100, 0, 0, 54, &fee);
+        let raw = query(deps.as_ref(), get_env(54), msg).unwrap();
+        let res: SimulateInstantClaimResponse = from_slice(&raw).unwrap();
+        assert_eq!(res, SimulateInstantClaimResponse::default());
+        let err = execute(deps.as_mut(), get_env(54), mock_info(USER1, &fee), msg_claim).unwrap_err();
+        assert_eq!(err, ContractError::NothingToClaim {});
+    }
+
+    #[test]
+    fn instant_claim_fee_split() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+        let fee = [Coin{ denom: "uusd".to_string(), amount: Uint128::new(1000000) }];
+        let staking_token = Denom::Cw20(Addr::unchecked(STAKING_TOKEN_ADDRESS));
+        fund_rewards(deps.as_mut(), 250_000_000_000);
+
+        let mut reward_streams = query_config(deps.as_ref()).unwrap().reward_streams;
+        reward_streams.push(RewardStream { token: staking_token.clone(), distribution_schedule: vec![] });
+        let mut new_config = NewConfig {
+            staking_token: None,
+            unbonding_period: None,
+            burn_address: None,
+            instant_claim_percentage_loss: Some(10),
+            reward_streams: Some(reward_streams),
+            fee_config: None,
+            lock_tiers: None,
+            early_unlock: None,
+            treasury_address: None,
+            instant_claim_split: Some(PenaltySplit { burn: 50, treasury: 30, stakers: 30 }),
+            instant_claim_curve: None,
+        };
+
+        // stakers share needs a stream paid in the staking token
+        let msg = ExecuteMsg::UpdateConfig(NewConfig {
+            reward_streams: None,
+            treasury_address: Some("treasury".into()),
+            instant_claim_split: Some(PenaltySplit { burn: 50, treasury: 30, stakers: 20 }),
+            ..new_config.clone()
+        });
+        let err = execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::StakersStreamNotFound {});
+
+        let msg = ExecuteMsg::UpdateConfig(new_config.clone());
+        let err = execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidPenaltySplit {});
+
+        new_config.instant_claim_split = Some(PenaltySplit { burn: 50, treasury: 30, stakers: 20 });
+        let msg = ExecuteMsg::UpdateConfig(new_config.clone());
+        let err = execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::TreasuryNotSet {});
+
+        new_config.treasury_address = Some("treasury".into());
+        let msg = ExecuteMsg::UpdateConfig(new_config.clone());
+        execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+
+        bond_cw20(deps.as_mut(), 1_000, 1_000, 0, 1);
+        unbond(deps.as_mut(), 1_000, 0, 0, 2, &fee);
+
+        // simulation splits the fee the same way as the claim
+        let msg = QueryMsg::SimulateInstantClaim { address: USER1.into(), amount: None };
+        let res: SimulateInstantClaimResponse = from_slice(&query(deps.as_ref(), get_env(2), msg).unwrap())
+            .unwrap();
+        assert_eq!(res, SimulateInstantClaimResponse {
+            amount: Uint128::new(900),
+            fee: Uint128::new(100),
+            burned: Uint128::new(50),
+            treasury: Uint128::new(30),
+            redistributed: Uint128::new(20),
+        });
+
+        // whole unbonding period left, so the full 10% is charged
+        let msg = ExecuteMsg::InstantClaim { amount: None };
+        let res = execute(deps.as_mut(), get_env(2), mock_info(USER1, &fee), msg).unwrap();
+        assert_eq!(res.messages, vec![
+            transfer_msg(&staking_token, &Addr::unchecked(USER1), Uint128::new(900)).unwrap(),
+            transfer_msg(&staking_token, &Addr::unchecked(BURN_ADDRESS), Uint128::new(50)).unwrap(),
+            transfer_msg(&staking_token, &Addr::unchecked("treasury"), Uint128::new(30)).unwrap(),
+        ]);
+        assert_eq!(res.attributes[5], attr("redistributed", format!("20 {}", STAKING_TOKEN_ADDRESS)));
+
+        // remaining staker gets the redistributed share
+        let member = query_staker(deps.as_ref(), get_env(2), USER2.into()).unwrap().member.unwrap();
+        assert_eq!(member.rewards[1].reward, Uint128::new(20));
+        let member = query_staker(deps.as_ref(), get_env(2), USER1.into()).unwrap().member.unwrap();
+        assert_eq!(member.rewards[1].reward, Uint128::zero());
+        let reserve = query_reward_reserve(deps.as_ref()).unwrap();
+        assert_eq!(reserve.streams[1].funded, Uint128::zero());
+        assert_eq!(reserve.streams[1].redistributed, Uint128::new(20));
+        assert_eq!(reserve.streams[1].owed, Uint128::new(20));
+
+        // flat curve charges the full percentage with half of the unbonding period left
+        unbond(deps.as_mut(), 0, 500, 0, 2, &fee);
+        let msg = QueryMsg::SimulateInstantClaim { address: USER2.into(), amount: None };
+        let res: SimulateInstantClaimResponse = from_slice(&query(deps.as_ref(), get_env(52), msg.clone()).unwrap())
+            .unwrap();
+        assert_eq!(res.fee, Uint128::new(25));
+        let msg_config = ExecuteMsg::UpdateConfig(NewConfig {
+            instant_claim_curve: Some(InstantClaimCurve::Flat {}),
+            ..new_config
+        });
+        execute(deps.as_mut(), get_env(52), mock_info(INIT_ADMIN, &[]), msg_config).unwrap();
+        let res: SimulateInstantClaimResponse = from_slice(&query(deps.as_ref(), get_env(52), msg).unwrap())
+            .unwrap();
+        assert_eq!(res.fee, Uint128::new(50));
+    }
+
+    #[test]
+    fn instant_claim_fee_kept_without_stakers() {
+        let mut deps = mock_dependencies(&[]);
+        default_instantiate(deps.as_mut(), mock_env());
+        let fee = [Coin{ denom: "uusd".to_string(), amount: Uint128::new(1000000) }];
+        let staking_token = Denom::Cw20(Addr::unchecked(STAKING_TOKEN_ADDRESS));
+
+        let mut reward_streams = query_config(deps.as_ref()).unwrap().reward_streams;
+        reward_streams.push(RewardStream { token: staking_token.clone(), distribution_schedule: vec![] });
+        let msg = ExecuteMsg::UpdateConfig(NewConfig {
+            staking_token: None,
+            unbonding_period: None,
+            burn_address: None,
+            instant_claim_percentage_loss: Some(10),
+            reward_streams: Some(reward_streams),
+            fee_config: None,
+            lock_tiers: None,
+            early_unlock: None,
+            treasury_address: None,
+            instant_claim_split: Some(PenaltySplit { burn: 80, treasury: 0, stakers: 20 }),
+            instant_claim_curve: None,
+        });
+        execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+
+        // the only staker leaves
+        bond_cw20(deps.as_mut(), 1_000, 0, 0, 1);
+        unbond(deps.as_mut(), 1_000, 0, 0, 2, &fee);
+        assert_eq!(query_state(deps.as_ref()).unwrap().total_stake, Uint128::zero());
+
+        // stakers share is not burned, but kept in the contract
+        let msg = ExecuteMsg::InstantClaim { amount: None };
+        let res = execute(deps.as_mut(), get_env(2), mock_info(USER1, &fee), msg).unwrap();
+        assert_eq!(res.messages, vec![
+            transfer_msg(&staking_token, &Addr::unchecked(USER1), Uint128::new(900)).unwrap(),
+            transfer_msg(&staking_token, &Addr::unchecked(BURN_ADDRESS), Uint128::new(80)).unwrap(),
+        ]);
+        assert_eq!(res.attributes[5], attr("redistributed", format!("20 {}", STAKING_TOKEN_ADDRESS)));
+        assert_eq!(query_state(deps.as_ref()).unwrap().undistributed, Uint128::new(20));
+        assert_eq!(query_reward_reserve(deps.as_ref()).unwrap().streams[1].owed, Uint128::new(20));
+
+        // and goes to the next stakers
+        bond_cw20(deps.as_mut(), 0, 500, 0, 3);
+        assert_eq!(query_state(deps.as_ref()).unwrap().undistributed, Uint128::zero());
+        let member = query_staker(deps.as_ref(), get_env(3), USER2.into()).unwrap().member.unwrap();
+        assert_eq!(member.rewards[1].reward, Uint128::new(20));
+    }
 }
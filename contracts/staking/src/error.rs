@@ -1,5 +1,7 @@
 use cosmwasm_std::StdError;
 use cw0::PaymentError;
+use cw_controllers::HookError;
+use ownership::{MigrationError, OwnershipError};
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -10,6 +12,15 @@ pub enum ContractError {
     #[error("{0}")]
     Payment(#[from] PaymentError),
 
+    #[error("{0}")]
+    Ownership(#[from] OwnershipError),
+
+    #[error("{0}")]
+    Migration(#[from] MigrationError),
+
+    #[error("{0}")]
+    Hook(#[from] HookError),
+
     #[error("No claims that can be released currently")]
     NothingToClaim {},
 
@@ -31,9 +42,63 @@ pub enum ContractError {
     #[error("No reward to release")]
     NothingToWithdraw {},
 
-    #[error("Cannot migrate from different contract type: {previous_contract}")]
-    CannotMigrate { previous_contract: String },
-
     #[error("Member not found")]
     MemberNotFound {},
+
+    #[error("Reward streams can only be appended, existing stream tokens cannot change")]
+    InvalidRewardStreams {},
+
+    #[error("Rewards can be compounded only when paid in the staking token")]
+    CompoundNotSupported {},
+
+    #[error("Lock tier needs positive duration and multiplier of at least one")]
+    InvalidLockTier {},
+
+    #[error("No lock tier with duration {0}")]
+    LockTierNotFound(u64),
+
+    #[error("Early unlock penalty cannot exceed 100 percent")]
+    InvalidEarlyUnlockPenalty {},
+
+    #[error("Stake is locked")]
+    StakeLocked {},
+
+    #[error("Amount exceeds pending claims")]
+    NotEnoughClaims {},
+
+    #[error("Schedule end time must be after its start time")]
+    InvalidSchedulePeriod {},
+
+    #[error("Schedules must be ordered and cannot overlap")]
+    OverlappingSchedules {},
+
+    #[error("Schedules which already started cannot change")]
+    ScheduleAlreadyStarted {},
+
+    #[error("No reward stream with index {0}")]
+    RewardStreamNotFound(u32),
+
+    #[error("No reward stream paid in {0}")]
+    UnknownRewardToken(String),
+
+    #[error("Schedules of reward stream {0} exceed funded amount")]
+    UnderfundedSchedule(u32),
+
+    #[error("Reward stream {0} pays in a cw20 token, fund it with FundRewards and append schedules afterwards")]
+    Cw20ScheduleOnInstantiate(u32),
+
+    #[error("Penalty split percentages must sum to 100")]
+    InvalidPenaltySplit {},
+
+    #[error("Treasury address is not set")]
+    TreasuryNotSet {},
+
+    #[error("Stakers share requires a reward stream paid in the staking token")]
+    StakersStreamNotFound {},
+
+    #[error("Funds are reserved for rewards and stakes")]
+    ReservedFunds {},
+
+    #[error("Staking token cannot change while there are stakes or claims")]
+    StakingTokenInUse {},
 }
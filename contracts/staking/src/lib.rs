@@ -1,5 +1,7 @@
+pub mod claims;
 pub mod contract;
 mod error;
+mod migrations;
 pub mod msg;
 pub mod state;
 
@@ -0,0 +1,310 @@
+use cosmwasm_std::{Addr, Decimal, DepsMut, Env, Order, StdError, StdResult, Uint128};
+use cw20::{BalanceResponse, Cw20QueryMsg, Denom};
+use cw_storage_plus::{Item, Map};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::contract::validate_schedules;
+use crate::error::ContractError;
+use crate::state::{
+    CLAIMS, CONFIG, Config, EarlyUnlock, FeeConfig, InstantClaimCurve, MEMBERS, MemberInfo, MemberReward,
+    PenaltySplit, RewardReserve, RewardStream, STAKE_SNAPSHOTS, STATE, Schedule, State, TOTAL_STAKE_KEY,
+    TOTAL_STAKE_SNAPSHOTS,
+};
+
+/// Config layout stored before 0.3.0
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct ConfigV0_2 {
+    owner: Addr,
+    staking_token: Addr,
+    terraland_token: Addr,
+    unbonding_period: u64,
+    burn_address: Addr,
+    instant_claim_percentage_loss: u64,
+    distribution_schedule: Vec<Schedule>,
+    fee_config: Vec<FeeConfig>,
+}
+
+/// State layout stored before 0.3.0
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct StateV0_2 {
+    total_stake: Uint128,
+    last_updated: u64,
+    global_reward_index: Decimal,
+    num_of_members: u64,
+}
+
+/// Member layout stored before 0.3.0
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct MemberInfoV0_2 {
+    stake: Uint128,
+    pending_reward: Uint128,
+    reward_index: Decimal,
+    withdrawn: Uint128,
+}
+
+const CONFIG_V0_2: Item<ConfigV0_2> = Item::new("config");
+const STATE_V0_2: Item<StateV0_2> = Item::new("state");
+const MEMBERS_V0_2: Map<&Addr, MemberInfoV0_2> = Map::new("members");
+
+/// Upgrades the state stored by the previous contract version to the current layout
+pub fn migrate_from(deps: DepsMut, env: &Env, previous: &Version) -> Result<(), ContractError> {
+    if *previous < Version::new(0, 3, 0) {
+        migrate_to_v0_3_0(deps, env)?;
+    }
+    Ok(())
+}
+
+/// Fails on invalid schedules or if the contract does not hold the scheduled rewards
+/// which were not paid yet
+fn migrate_to_v0_3_0(deps: DepsMut, env: &Env) -> Result<(), ContractError> {
+    let height = env.block.height;
+    let config = CONFIG_V0_2.load(deps.storage)?;
+    validate_schedules(&config.distribution_schedule)?;
+    let state = STATE_V0_2.load(deps.storage)?;
+    let members = MEMBERS_V0_2
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    // configured schedule was funded before reserves were tracked, rewards not paid yet
+    // have to be held beside the stakes and claims when they are in the same token
+    let scheduled: Uint128 = config.distribution_schedule.iter().map(|schedule| schedule.amount).sum();
+    let paid: Uint128 = members.iter().map(|(_, member)| member.withdrawn).sum();
+    let balance: BalanceResponse = deps.querier.query_wasm_smart(
+        &config.terraland_token,
+        &Cw20QueryMsg::Balance { address: env.contract.address.to_string() },
+    )?;
+    let mut available = balance.balance;
+    if config.staking_token == config.terraland_token {
+        available = available
+            .checked_sub(state.total_stake + CLAIMS.total_pending(deps.storage)?)
+            .unwrap_or_default();
+    }
+    if scheduled.checked_sub(paid).unwrap_or_default() > available {
+        return Err(ContractError::UnderfundedSchedule(0));
+    }
+
+    // the single terraland reward schedule becomes the first reward stream
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            reward_streams: vec![RewardStream {
+                token: Denom::Cw20(config.terraland_token.clone()),
+                distribution_schedule: config.distribution_schedule,
+            }],
+            owner: config.owner,
+            staking_token: Denom::Cw20(config.staking_token),
+            terraland_token: config.terraland_token,
+            unbonding_period: config.unbonding_period,
+            burn_address: config.burn_address,
+            instant_claim_percentage_loss: config.instant_claim_percentage_loss,
+            fee_config: config.fee_config,
+            lock_tiers: vec![],
+            early_unlock: EarlyUnlock::Forbidden {},
+            treasury_address: None,
+            instant_claim_split: PenaltySplit::default(),
+            instant_claim_curve: InstantClaimCurve::default(),
+        },
+    )?;
+
+    for (key, member) in members {
+        let addr = Addr::unchecked(String::from_utf8(key).map_err(StdError::from)?);
+        // stake snapshots start at the migration height
+        STAKE_SNAPSHOTS.save(deps.storage, &addr, &member.stake, height)?;
+        MEMBERS.save(
+            deps.storage,
+            &addr,
+            &MemberInfo {
+                stake: member.stake,
+                rewards: vec![MemberReward {
+                    pending_reward: member.pending_reward,
+                    reward_index: member.reward_index,
+                    withdrawn: member.withdrawn,
+                }],
+                auto_compound: false,
+                locks: vec![],
+            },
+        )?;
+    }
+
+    STATE.save(
+        deps.storage,
+        &State {
+            total_stake: state.total_stake,
+            last_updated: state.last_updated,
+            global_reward_indices: vec![state.global_reward_index],
+            num_of_members: state.num_of_members,
+            reward_reserves: vec![RewardReserve { funded: scheduled, paid, ..Default::default() }],
+            undistributed: Uint128::zero(),
+        },
+    )?;
+    TOTAL_STAKE_SNAPSHOTS.save(deps.storage, TOTAL_STAKE_KEY, &state.total_stake, height)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, MockApi, MockStorage};
+    use cosmwasm_std::{to_binary, ContractResult, OwnedDeps, Querier, QuerierResult, Storage, SystemResult};
+    use cw2::{get_contract_version, set_contract_version};
+    use cw_controllers::Claim;
+    use cw0::Expiration;
+    use ownership::MigrationError;
+
+    use crate::contract::{migrate, query_config};
+    use crate::msg::MigrateMsg;
+
+    use super::*;
+
+    const CONTRACT_NAME: &str = "crates.io:fcq-staking";
+
+    /// Answers every query with the given cw20 balance
+    struct Cw20BalanceQuerier(Uint128);
+
+    impl Querier for Cw20BalanceQuerier {
+        fn raw_query(&self, _bin_request: &[u8]) -> QuerierResult {
+            SystemResult::Ok(ContractResult::Ok(to_binary(&BalanceResponse { balance: self.0 }).unwrap()))
+        }
+    }
+
+    /// Contract as stored by 0.2.0, holding the given balance of the terraland token
+    fn deps_v0_2(staking_token: &str, balance: u128) -> OwnedDeps<MockStorage, MockApi, Cw20BalanceQuerier> {
+        let mut deps = OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: Cw20BalanceQuerier(Uint128::new(balance)),
+        };
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "0.2.0").unwrap();
+        let config = format!(
+            r#"{{"owner":"admin","staking_token":"{}","terraland_token":"tland1234567890",
+                "unbonding_period":600,"burn_address":"burn1234567890","instant_claim_percentage_loss":0,
+                "distribution_schedule":[{{"amount":"100","start_time":1,"end_time":2}}],"fee_config":[]}}"#,
+            staking_token,
+        );
+        deps.storage.set(b"config", config.as_bytes());
+        deps.storage.set(
+            b"state",
+            br#"{"total_stake":"10","last_updated":5,"global_reward_index":"0.5","num_of_members":1}"#,
+        );
+        MEMBERS_V0_2
+            .save(
+                &mut deps.storage,
+                &Addr::unchecked("somebody"),
+                &MemberInfoV0_2 {
+                    stake: Uint128::new(10),
+                    pending_reward: Uint128::new(5),
+                    reward_index: Decimal::percent(50),
+                    withdrawn: Uint128::new(2),
+                },
+            )
+            .unwrap();
+        deps
+    }
+
+    #[test]
+    fn migrate_from_v0_2() {
+        // scheduled rewards not paid yet are held by the contract
+        let mut deps = deps_v0_2("staking1234567890", 98);
+        let member = Addr::unchecked("somebody");
+
+        let env = mock_env();
+        let res = migrate(deps.as_mut(), env.clone(), MigrateMsg {}).unwrap();
+        assert_eq!(res.attributes[1].value, "0.2.0");
+        assert_eq!(
+            get_contract_version(&deps.storage).unwrap().version,
+            env!("CARGO_PKG_VERSION")
+        );
+
+        let config = query_config(deps.as_ref()).unwrap();
+        assert_eq!(config.owner, "admin");
+        assert_eq!(
+            config.reward_streams,
+            vec![RewardStream {
+                token: Denom::Cw20(Addr::unchecked("tland1234567890")),
+                distribution_schedule: vec![Schedule {
+                    amount: Uint128::new(100),
+                    start_time: 1,
+                    end_time: 2,
+                }],
+            }]
+        );
+        let state = STATE.load(&deps.storage).unwrap();
+        assert_eq!(state.global_reward_indices, vec![Decimal::percent(50)]);
+        assert_eq!(state.num_of_members, 1);
+        assert_eq!(
+            state.reward_reserves,
+            vec![RewardReserve { funded: Uint128::new(100), paid: Uint128::new(2), ..Default::default() }]
+        );
+        assert_eq!(
+            MEMBERS.load(&deps.storage, &member).unwrap(),
+            MemberInfo {
+                stake: Uint128::new(10),
+                rewards: vec![MemberReward {
+                    pending_reward: Uint128::new(5),
+                    reward_index: Decimal::percent(50),
+                    withdrawn: Uint128::new(2),
+                }],
+                auto_compound: false,
+                locks: vec![],
+            }
+        );
+        let height = env.block.height + 1;
+        assert_eq!(
+            STAKE_SNAPSHOTS.may_load_at_height(&deps.storage, &member, height).unwrap(),
+            Some(Uint128::new(10))
+        );
+        assert_eq!(
+            TOTAL_STAKE_SNAPSHOTS.may_load_at_height(&deps.storage, TOTAL_STAKE_KEY, height).unwrap(),
+            Some(Uint128::new(10))
+        );
+    }
+
+    #[test]
+    fn migrate_underfunded_schedule() {
+        let err = migrate(deps_v0_2("staking1234567890", 97).as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+        assert_eq!(err, ContractError::UnderfundedSchedule(0));
+
+        // stakes and claims in the terraland token are not available for rewards
+        let mut deps = deps_v0_2("tland1234567890", 110);
+        CLAIMS.create_claim(&mut deps.storage, &Addr::unchecked("else"), Uint128::new(3), Expiration::Never {})
+            .unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+        assert_eq!(err, ContractError::UnderfundedSchedule(0));
+        assert_eq!(get_contract_version(&deps.storage).unwrap().version, "0.2.0");
+
+        let mut deps = deps_v0_2("tland1234567890", 111);
+        CLAIMS.create_claim(&mut deps.storage, &Addr::unchecked("else"), Uint128::new(3), Expiration::Never {})
+            .unwrap();
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(
+            CLAIMS.query_claims(deps.as_ref(), &Addr::unchecked("else")).unwrap().claims,
+            vec![Claim { amount: Uint128::new(3), release_at: Expiration::Never {} }]
+        );
+    }
+
+    #[test]
+    fn migrate_invalid_schedule() {
+        let mut deps = deps_v0_2("staking1234567890", 98);
+        let mut config = CONFIG_V0_2.load(&deps.storage).unwrap();
+        config.distribution_schedule[0].end_time = config.distribution_schedule[0].start_time;
+        CONFIG_V0_2.save(&mut deps.storage, &config).unwrap();
+
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+        assert_eq!(err, ContractError::InvalidSchedulePeriod {});
+        assert_eq!(get_contract_version(&deps.storage).unwrap().version, "0.2.0");
+    }
+
+    #[test]
+    fn refuse_downgrade() {
+        let mut deps = mock_dependencies(&[]);
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "99.0.0").unwrap();
+
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Migration(MigrationError::CannotMigrateVersion {
+                previous_version: "99.0.0".to_string()
+            })
+        );
+    }
+}
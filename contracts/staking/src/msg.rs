@@ -1,33 +1,53 @@
 use cosmwasm_std::{Decimal, Uint128};
-use cw20::Cw20ReceiveMsg;
+use cw0::Expiration;
+use cw20::{Cw20ReceiveMsg, Denom};
 use cw_controllers::Claim;
 pub use cw_controllers::ClaimsResponse;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::state::{FeeConfig, Schedule};
+use crate::state::{EarlyUnlock, FeeConfig, InstantClaimCurve, Lock, LockTier, PenaltySplit, RewardStream, Schedule};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
     pub owner: String,
-    pub staking_token: String,
+    pub staking_token: Denom,
     pub terraland_token: String,
     pub unbonding_period: u64,
     pub burn_address: String,
     pub instant_claim_percentage_loss: u64,
-    pub distribution_schedule: Vec<Schedule>,
+    /// Schedules of native streams are funded by the coins sent along. Cw20 streams start
+    /// without schedules, they are appended once the tokens are deposited with FundRewards
+    pub reward_streams: Vec<RewardStream>,
     pub fee_config: Vec<FeeConfig>,
+    #[serde(default)]
+    pub lock_tiers: Vec<LockTier>,
+    #[serde(default)]
+    pub early_unlock: EarlyUnlock,
+    #[serde(default)]
+    pub treasury_address: Option<String>,
+    #[serde(default)]
+    pub instant_claim_split: PenaltySplit,
+    #[serde(default)]
+    pub instant_claim_curve: InstantClaimCurve,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct NewConfig {
-    pub owner: Option<String>,
-    pub staking_token: Option<String>,
+    /// Can change only while nothing is staked or waiting to be claimed
+    pub staking_token: Option<Denom>,
     pub unbonding_period: Option<u64>,
     pub burn_address: Option<String>,
     pub instant_claim_percentage_loss: Option<u64>,
-    pub distribution_schedule: Option<Vec<Schedule>>,
+    /// Existing streams keep their tokens, new streams can only be appended
+    pub reward_streams: Option<Vec<RewardStream>>,
     pub fee_config: Option<Vec<FeeConfig>>,
+    pub lock_tiers: Option<Vec<LockTier>>,
+    pub early_unlock: Option<EarlyUnlock>,
+    pub treasury_address: Option<String>,
+    /// Percentages have to sum to 100, treasury share requires treasury address
+    pub instant_claim_split: Option<PenaltySplit>,
+    pub instant_claim_curve: Option<InstantClaimCurve>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -35,20 +55,54 @@ pub struct NewConfig {
 pub enum ExecuteMsg {
     /// Update config parameters
     UpdateConfig ( NewConfig ),
+    /// Add schedule starting in the future to the end of the reward stream
+    AppendSchedule {
+        stream: u32,
+        schedule: Schedule,
+    },
+    /// Propose new owner, who has to accept the ownership before expiry
+    ProposeNewOwner {
+        owner: String,
+        expiry: Option<Expiration>,
+    },
+    /// Accept the ownership proposed to the sender
+    AcceptOwnership {},
+    /// Cancel pending ownership proposal
+    CancelOwnershipProposal {},
     /// Unbond will start the unbonding process for the given number of tokens.
     /// The sender immediately loses weight from these tokens, and can claim them
-    /// back to his wallet after `unbonding_period`
+    /// back to his wallet after `unbonding_period`. Unlocked stake is used first,
+    /// locked one only if `early_unlock` allows it
     Unbond { tokens: Uint128 },
+    /// Rebond moves tokens from pending claims back to the stake, newest claims first.
+    /// All pending claims are rebonded if amount is not set
+    Rebond { amount: Option<Uint128> },
     /// Claim is used to claim your native tokens that you previously "unbonded"
-    /// after the contract-defined waiting period (eg. 1 week)
-    Claim {},
-    /// Claim without waiting period, but with percentage fee
-    InstantClaim {},
-    /// Withdraw reward
+    /// after the contract-defined waiting period (eg. 1 week).
+    /// Oldest claims are released first, all mature ones if amount is not set
+    Claim { amount: Option<Uint128> },
+    /// Claim without waiting period, but with fee charged on the released amount.
+    /// Fee of every claim is `instant_claim_percentage_loss` scaled by its remaining part
+    /// of the unbonding period. Oldest claims are released first, all pending ones if amount is not set
+    InstantClaim { amount: Option<Uint128> },
+    /// Withdraw rewards of all reward streams
     Withdraw {},
+    /// Add reward paid in the staking token to the stake, without any transfer
+    Compound {},
+    /// Compound reward paid in the staking token on every bond and unbond
+    SetAutoCompound { enabled: bool },
 
     /// This accepts a properly-encoded ReceiveMsg from a cw20 contract
     Receive(Cw20ReceiveMsg),
+    /// Bond native coins, when staking token is a native denom
+    Bond { lock: Option<u64> },
+    /// Deposit native coins paying rewards of the reward streams
+    FundRewards {},
+
+    /// Add contract notified with `MemberChangedHookMsg` on every stake weight change
+    AddHook { addr: String },
+    /// Remove contract from the notified ones
+    RemoveHook { addr: String },
 
     /// Withdraw ust from smart contract by owner
     UstWithdraw {
@@ -68,8 +122,12 @@ pub struct MigrateMsg {}
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ReceiveMsg {
-    /// Only valid cw20 message is to bond the tokens
-    Bond {},
+    /// Bond the tokens, optionally locked for the duration of one of the lock tiers
+    Bond { lock: Option<u64> },
+    /// Bond the tokens into the stake of the beneficiary
+    BondFor { beneficiary: String },
+    /// Deposit tokens paying rewards of the reward stream
+    FundRewards {},
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
@@ -78,40 +136,163 @@ pub enum QueryMsg {
     /// Return config
     Config {},
 
+    /// Return owner proposed by the current one
+    PendingOwner {},
+
     /// Return state
     State {},
 
     /// Return staker info
-    Member { address: String },
+    Staker { address: String },
 
     /// Return stakers
+    ListStakers {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Return current annual reward per staked token of every reward stream
+    Apr {},
+
+    /// Return staker rewards as they will be at the given time, if stake does not change
+    SimulateReward { address: String, at_time: u64 },
+
+    /// Return distributed and remaining amount of every schedule
+    DistributionStatus {},
+
+    /// Return funded, scheduled and paid amount of every reward stream
+    RewardReserve {},
+
+    /// Return cw4 MemberResponse with the stake weight of the address
+    Member {
+        addr: String,
+        at_height: Option<u64>,
+    },
+    /// Return cw4 MemberListResponse with stake weights
     ListMembers {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    /// Return cw4 TotalWeightResponse
+    TotalWeight {},
+    /// Return cw4 HooksResponse
+    Hooks {},
+
+    /// Return stake weight of the address at the beginning of the block height
+    StakeAtHeight { address: String, height: u64 },
+    /// Return total stake weight at the beginning of the block height
+    TotalStakeAtHeight { height: u64 },
+
+    /// Return amount paid out and fee charged if claims of the address were instant claimed now,
+    /// up to the amount like InstantClaim does, all pending ones if amount is not set
+    SimulateInstantClaim { address: String, amount: Option<Uint128> },
 }
 
 #[derive(Default, Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
-pub struct MemberResponseItem {
+pub struct StakerResponseItem {
     pub stake: Uint128,
+    pub rewards: Vec<MemberRewardResponse>,
+    pub auto_compound: bool,
+    pub locks: Vec<Lock>,
+    pub claims: Vec<Claim>,
+}
+
+#[derive(Default, Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct MemberRewardResponse {
+    pub token: Denom,
     pub reward: Uint128,
     pub reward_index: Decimal,
     pub withdrawn: Uint128,
-    pub claims: Vec<Claim>,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
-pub struct MemberListResponseItem {
+pub struct StakerListResponseItem {
     pub address: String,
-    pub info: MemberResponseItem,
+    pub info: StakerResponseItem,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct StakerResponse {
+    pub member: Option<StakerResponseItem>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct StakerListResponse {
+    pub members: Vec<StakerListResponseItem>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct AprResponse {
+    pub streams: Vec<StreamAprResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct StreamAprResponse {
+    pub token: Denom,
+    pub apr: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SimulateRewardResponse {
+    pub rewards: Vec<MemberRewardResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct DistributionStatusResponse {
+    pub streams: Vec<StreamDistributionStatus>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct StreamDistributionStatus {
+    pub token: Denom,
+    pub schedules: Vec<ScheduleStatus>,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
-pub struct MemberResponse {
-    pub member: Option<MemberResponseItem>,
+pub struct ScheduleStatus {
+    pub amount: Uint128,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub amount_per_sec: Uint128,
+    pub distributed: Uint128,
+    pub remaining: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
-pub struct MemberListResponse {
-    pub members: Vec<MemberListResponseItem>,
+pub struct RewardReserveResponse {
+    pub streams: Vec<StreamReserveResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct StreamReserveResponse {
+    pub token: Denom,
+    pub funded: Uint128,
+    pub scheduled: Uint128,
+    pub redistributed: Uint128,
+    pub paid: Uint128,
+    pub compounded: Uint128,
+    /// Scheduled and redistributed amount not paid or compounded yet
+    pub owed: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct StakeAtHeightResponse {
+    pub stake: Uint128,
+    pub height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TotalStakeAtHeightResponse {
+    pub total_stake: Uint128,
+    pub height: u64,
+}
+
+#[derive(Default, Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SimulateInstantClaimResponse {
+    pub amount: Uint128,
+    pub fee: Uint128,
+    /// Parts of the fee sent to the burn address, the treasury and shared by stakers
+    pub burned: Uint128,
+    pub treasury: Uint128,
+    pub redistributed: Uint128,
 }
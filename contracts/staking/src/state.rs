@@ -1,19 +1,91 @@
-use cosmwasm_std::{Addr, Decimal, Uint128};
-use cw_controllers::Claims;
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Addr, Decimal, Empty, Uint128};
+use cw20::Denom;
+use cw_controllers::Hooks;
+use cw_storage_plus::{Item, Map, SnapshotMap, Strategy, U64Key};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::claims::Claims;
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct Config {
     pub owner: Addr,
-    pub staking_token: Addr,
+    pub staking_token: Denom,
     pub terraland_token: Addr,
     pub unbonding_period: u64,
     pub burn_address: Addr,
     pub instant_claim_percentage_loss: u64,
-    pub distribution_schedule: Vec<Schedule>,
+    pub reward_streams: Vec<RewardStream>,
     pub fee_config: Vec<FeeConfig>,
+    #[serde(default)]
+    pub lock_tiers: Vec<LockTier>,
+    #[serde(default)]
+    pub early_unlock: EarlyUnlock,
+    #[serde(default)]
+    pub treasury_address: Option<Addr>,
+    #[serde(default)]
+    pub instant_claim_split: PenaltySplit,
+    #[serde(default)]
+    pub instant_claim_curve: InstantClaimCurve,
+}
+
+/// Lock period in seconds with the multiplier applied to reward weight of tokens locked for it
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct LockTier {
+    pub duration: u64,
+    pub multiplier: Decimal,
+}
+
+/// What happens when locked tokens are unbonded before the lock ends
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum EarlyUnlock {
+    Forbidden {},
+    /// Percentage of unlocked tokens sent to the burn address
+    Penalty { percentage: u64 },
+}
+
+impl Default for EarlyUnlock {
+    fn default() -> Self {
+        EarlyUnlock::Forbidden {}
+    }
+}
+
+/// Percentages of the instant claim fee burned, sent to the treasury and redistributed to stakers
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PenaltySplit {
+    pub burn: u64,
+    pub treasury: u64,
+    pub stakers: u64,
+}
+
+impl Default for PenaltySplit {
+    fn default() -> Self {
+        PenaltySplit { burn: 100, treasury: 0, stakers: 0 }
+    }
+}
+
+/// How the instant claim fee depends on the unbonding time left on the claim
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum InstantClaimCurve {
+    /// Fee falls linearly from the full percentage to zero at the release time
+    Linear {},
+    /// Full percentage is charged whatever time is left
+    Flat {},
+}
+
+impl Default for InstantClaimCurve {
+    fn default() -> Self {
+        InstantClaimCurve::Linear {}
+    }
+}
+
+/// Reward token distributed to stakers according to its own schedule
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct RewardStream {
+    pub token: Denom,
+    pub distribution_schedule: Vec<Schedule>,
 }
 
 #[derive(Default, Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
@@ -33,6 +105,43 @@ pub struct FeeConfig {
 #[derive(Default, Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct MemberInfo {
     pub stake: Uint128,
+    /// Rewards per stream, in the order of `Config.reward_streams`
+    pub rewards: Vec<MemberReward>,
+    /// Reward paid in the staking token is added to the stake on bond and unbond
+    #[serde(default)]
+    pub auto_compound: bool,
+    /// Part of the stake locked for one of the lock tiers
+    #[serde(default)]
+    pub locks: Vec<Lock>,
+}
+
+impl MemberInfo {
+    /// Stake weight used for rewards, locked tokens count with their tier multiplier
+    pub fn weight(&self) -> Uint128 {
+        self.stake + self.locks.iter().map(Lock::boost).sum::<Uint128>()
+    }
+
+    pub fn locked(&self) -> Uint128 {
+        self.locks.iter().map(|lock| lock.amount).sum()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Lock {
+    pub amount: Uint128,
+    pub multiplier: Decimal,
+    pub unlock_at: u64,
+}
+
+impl Lock {
+    /// Weight added on top of the locked amount until the lock ends
+    pub fn boost(&self) -> Uint128 {
+        self.amount * self.multiplier - self.amount
+    }
+}
+
+#[derive(Default, Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct MemberReward {
     pub pending_reward: Uint128,
     pub reward_index: Decimal,
     pub withdrawn: Uint128,
@@ -40,13 +149,82 @@ pub struct MemberInfo {
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct State {
+    /// Sum of member weights, locked stake is counted with its tier multiplier
     pub total_stake: Uint128,
     pub last_updated: u64,
-    pub global_reward_index: Decimal,
+    /// Reward index per stream, in the order of `Config.reward_streams`
+    pub global_reward_indices: Vec<Decimal>,
     pub num_of_members: u64,
+    /// Reward reserve per stream, in the order of `Config.reward_streams`
+    #[serde(default)]
+    pub reward_reserves: Vec<RewardReserve>,
+    /// Stakers share of instant claim fees received while there was no stake
+    #[serde(default)]
+    pub undistributed: Uint128,
+}
+
+impl State {
+    pub fn reward_reserve(&self, stream: usize) -> RewardReserve {
+        self.reward_reserves.get(stream).cloned().unwrap_or_default()
+    }
+
+    pub fn reward_reserve_mut(&mut self, stream: usize) -> &mut RewardReserve {
+        if self.reward_reserves.len() <= stream {
+            self.reward_reserves.resize(stream + 1, RewardReserve::default());
+        }
+        &mut self.reward_reserves[stream]
+    }
+}
+
+/// Reward tokens deposited for the stream and paid out of it
+#[derive(Default, Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct RewardReserve {
+    /// Deposits backing the distribution schedules
+    pub funded: Uint128,
+    /// Withdrawn by members
+    pub paid: Uint128,
+    /// Moved into member stakes
+    #[serde(default)]
+    pub compounded: Uint128,
+    /// Instant claim fees shared by stakers on top of the schedules
+    #[serde(default)]
+    pub redistributed: Uint128,
+}
+
+impl RewardReserve {
+    /// Scheduled and redistributed rewards not withdrawn or compounded yet
+    pub fn owed(&self, scheduled: Uint128) -> Uint128 {
+        (scheduled + self.redistributed)
+            .checked_sub(self.paid + self.compounded)
+            .unwrap_or_default()
+    }
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
 pub const MEMBERS: Map<&Addr, MemberInfo> = Map::new("members");
 pub const STATE: Item<State> = Item::new("state");
 pub const CLAIMS: Claims = Claims::new("claims");
+pub const HOOKS: Hooks = Hooks::new("hooks");
+
+/// Lock boosts by unlock time, removed from the total stake once the time passes
+pub const LOCK_EXPIRIES: Map<U64Key, Uint128> = Map::new("lock_expiries");
+/// Members holding locks by unlock time, their boost is released once the time passes
+pub const LOCK_HOLDERS: Map<(U64Key, &Addr), Empty> = Map::new("lock_holders");
+/// Global reward indices at the unlock times already removed from the total stake
+pub const UNLOCK_INDICES: Map<U64Key, Vec<Decimal>> = Map::new("unlock_indices");
+
+/// Member weights by block height
+pub const STAKE_SNAPSHOTS: SnapshotMap<&Addr, Uint128> = SnapshotMap::new(
+    "stake_snapshots",
+    "stake_snapshots__checkpoints",
+    "stake_snapshots__changelog",
+    Strategy::EveryBlock,
+);
+/// Total stake by block height, stored under `TOTAL_STAKE_KEY`
+pub const TOTAL_STAKE_SNAPSHOTS: SnapshotMap<&str, Uint128> = SnapshotMap::new(
+    "total_stake_snapshots",
+    "total_stake_snapshots__checkpoints",
+    "total_stake_snapshots__changelog",
+    Strategy::EveryBlock,
+);
+pub const TOTAL_STAKE_KEY: &str = "total";
@@ -5,9 +5,14 @@ use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
 use cw20::{
     AllAccountsResponse, AllAllowancesResponse, AllowanceResponse, BalanceResponse,
-    TokenInfoResponse,
+    MinterResponse, TokenInfoResponse,
 };
-use tland_token::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, MigrateMsg};
+use tland_token::msg::{
+    AllSpenderAllowancesResponse, DelegatesResponse, ExecuteMsg, FrozenAccountsResponse,
+    InstantiateMsg, QueryMsg, MigrateMsg, PermitNonceResponse, PermitPayload,
+    SimulateTransferResponse, TotalSupplyResponse, VotingPowerResponse,
+};
+use ownership::PendingOwnerResponse;
 use tland_token::state::Config as ConfigResponse;
 
 fn main() {
@@ -24,7 +29,17 @@ fn main() {
     export_schema(&schema_for!(AllowanceResponse), &out_dir);
     export_schema(&schema_for!(BalanceResponse), &out_dir);
     export_schema(&schema_for!(TokenInfoResponse), &out_dir);
+    export_schema(&schema_for!(MinterResponse), &out_dir);
+    export_schema(&schema_for!(TotalSupplyResponse), &out_dir);
+    export_schema(&schema_for!(FrozenAccountsResponse), &out_dir);
+    export_schema(&schema_for!(PermitPayload), &out_dir);
+    export_schema(&schema_for!(PermitNonceResponse), &out_dir);
+    export_schema(&schema_for!(SimulateTransferResponse), &out_dir);
+    export_schema(&schema_for!(VotingPowerResponse), &out_dir);
+    export_schema(&schema_for!(DelegatesResponse), &out_dir);
     export_schema(&schema_for!(AllAllowancesResponse), &out_dir);
+    export_schema(&schema_for!(AllSpenderAllowancesResponse), &out_dir);
     export_schema(&schema_for!(AllAccountsResponse), &out_dir);
     export_schema(&schema_for!(ConfigResponse), &out_dir);
+    export_schema(&schema_for!(PendingOwnerResponse), &out_dir);
 }
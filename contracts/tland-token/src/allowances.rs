@@ -1,11 +1,22 @@
+use bech32::{ToBase32, Variant};
 use cosmwasm_std::{
-    attr, Addr, Binary, BlockInfo, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
-    Storage, Uint128,
+    attr, to_vec, Addr, Binary, BlockInfo, Deps, DepsMut, Env, MessageInfo, Response, StdError,
+    StdResult, Storage, Uint128,
 };
 use cw20::{AllowanceResponse, Cw20ReceiveMsg, Expiration};
+use ripemd160::Ripemd160;
+use sha2::{Digest, Sha256};
 
+use crate::contract::{
+    assert_can_receive, assert_not_frozen, assert_not_paused, charge_transfer_fee,
+};
 use crate::error::ContractError;
-use crate::state::{ALLOWANCES, BALANCES, CONFIG, TOKEN_INFO};
+use crate::msg::{PermitNonceResponse, PermitPayload};
+use crate::state::{
+    ALLOWANCES, ALLOWANCES_SPENDER, BALANCES, CONFIG, PERMIT_NONCES, TOKEN_INFO,
+    TOTAL_SUPPLY_HISTORY,
+};
+use crate::voting::move_voting_power;
 
 pub fn execute_increase_allowance(
     deps: DepsMut,
@@ -19,8 +30,9 @@ pub fn execute_increase_allowance(
     if spender_addr == info.sender {
         return Err(ContractError::CannotSetOwnAccount {});
     }
+    assert_not_frozen(deps.storage, &info.sender)?;
 
-    ALLOWANCES.update(
+    let allowance = ALLOWANCES.update(
         deps.storage,
         (&info.sender, &spender_addr),
         |allow| -> StdResult<_> {
@@ -32,6 +44,7 @@ pub fn execute_increase_allowance(
             Ok(val)
         },
     )?;
+    ALLOWANCES_SPENDER.save(deps.storage, (&spender_addr, &info.sender), &allowance)?;
 
     let res = Response::new().add_attributes(vec![
         attr("action", "increase_allowance"),
@@ -68,8 +81,10 @@ pub fn execute_decrease_allowance(
             allowance.expires = exp;
         }
         ALLOWANCES.save(deps.storage, key, &allowance)?;
+        ALLOWANCES_SPENDER.save(deps.storage, (&spender_addr, &info.sender), &allowance)?;
     } else {
         ALLOWANCES.remove(deps.storage, key);
+        ALLOWANCES_SPENDER.remove(deps.storage, (&spender_addr, &info.sender));
     }
 
     let res = Response::new().add_attributes(vec![
@@ -81,6 +96,90 @@ pub fn execute_decrease_allowance(
     Ok(res)
 }
 
+#[allow(clippy::too_many_arguments)]
+pub fn execute_permit(
+    deps: DepsMut,
+    env: Env,
+    owner: String,
+    spender: String,
+    amount: Uint128,
+    expires: Option<Expiration>,
+    nonce: u64,
+    signature: Binary,
+    pubkey: Binary,
+) -> Result<Response, ContractError> {
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    if spender_addr == owner_addr {
+        return Err(ContractError::CannotSetOwnAccount {});
+    }
+    assert_not_frozen(deps.storage, &owner_addr)?;
+
+    if let Some(exp) = expires {
+        if exp.is_expired(&env.block) {
+            return Err(ContractError::Expired {});
+        }
+    }
+
+    // every nonce can be used only once and in order
+    let expected = PERMIT_NONCES
+        .may_load(deps.storage, &owner_addr)?
+        .unwrap_or_default();
+    if nonce != expected {
+        return Err(ContractError::InvalidPermitNonce { expected });
+    }
+
+    // the pubkey has to belong to the owner
+    if pubkey_to_address(owner_addr.as_str(), &pubkey)? != owner_addr.as_str() {
+        return Err(ContractError::InvalidPermitSignature {});
+    }
+
+    let payload = PermitPayload {
+        chain_id: env.block.chain_id.clone(),
+        contract: env.contract.address.to_string(),
+        owner: owner.clone(),
+        spender: spender.clone(),
+        amount,
+        expires,
+        nonce,
+    };
+    let hash = Sha256::digest(&to_vec(&payload)?);
+    let valid = deps
+        .api
+        .secp256k1_verify(&hash, &signature, &pubkey)
+        .map_err(|_| ContractError::InvalidPermitSignature {})?;
+    if !valid {
+        return Err(ContractError::InvalidPermitSignature {});
+    }
+
+    PERMIT_NONCES.save(deps.storage, &owner_addr, &(nonce + 1))?;
+    let allowance = AllowanceResponse {
+        allowance: amount,
+        expires: expires.unwrap_or_default(),
+    };
+    ALLOWANCES.save(deps.storage, (&owner_addr, &spender_addr), &allowance)?;
+    ALLOWANCES_SPENDER.save(deps.storage, (&spender_addr, &owner_addr), &allowance)?;
+
+    let res = Response::new().add_attributes(vec![
+        attr("action", "permit"),
+        attr("owner", owner),
+        attr("spender", spender),
+        attr("amount", amount),
+        attr("nonce", nonce.to_string()),
+    ]);
+    Ok(res)
+}
+
+/// Derives the bech32 account address of the compressed secp256k1 pubkey,
+/// using the same prefix as the given address
+fn pubkey_to_address(address: &str, pubkey: &[u8]) -> Result<String, ContractError> {
+    let (hrp, _, _) =
+        bech32::decode(address).map_err(|_| ContractError::InvalidPermitSignature {})?;
+    let hash = Ripemd160::digest(&Sha256::digest(pubkey));
+    bech32::encode(&hrp, hash.to_base32(), Variant::Bech32)
+        .map_err(|_| ContractError::InvalidPermitSignature {})
+}
+
 // this can be used to update a lower allowance - call bucket.update with proper keys
 pub fn deduct_allowance(
     storage: &mut dyn Storage,
@@ -89,7 +188,7 @@ pub fn deduct_allowance(
     block: &BlockInfo,
     amount: Uint128,
 ) -> Result<AllowanceResponse, ContractError> {
-    ALLOWANCES.update(storage, (owner, spender), |current| {
+    let allowance = ALLOWANCES.update(storage, (owner, spender), |current| {
         match current {
             Some(mut a) => {
                 if a.expires.is_expired(block) {
@@ -105,7 +204,9 @@ pub fn deduct_allowance(
             }
             None => Err(ContractError::NoAllowance {}),
         }
-    })
+    })?;
+    ALLOWANCES_SPENDER.save(storage, (spender, owner), &allowance)?;
+    Ok(allowance)
 }
 
 pub fn execute_transfer_from(
@@ -119,20 +220,36 @@ pub fn execute_transfer_from(
     let rcpt_addr = deps.api.addr_validate(&recipient)?;
     let owner_addr = deps.api.addr_validate(&owner)?;
 
+    assert_not_paused(deps.storage, &info.sender)?;
+    assert_not_frozen(deps.storage, &owner_addr)?;
+    assert_not_frozen(deps.storage, &info.sender)?;
+    assert_can_receive(deps.storage, &rcpt_addr)?;
+
     // deduct allowance before doing anything else have enough allowance
     deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
 
     BALANCES.update(
         deps.storage,
         &owner_addr,
+        env.block.height,
         |balance: Option<Uint128>| -> StdResult<_> {
             Ok(balance.unwrap_or_default().checked_sub(amount)?)
         },
     )?;
+    let fee = charge_transfer_fee(deps.storage, env.block.height, &owner_addr, &rcpt_addr, amount)?;
+    let received = amount.checked_sub(fee)?;
     BALANCES.update(
         deps.storage,
         &rcpt_addr,
-        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
+        env.block.height,
+        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + received) },
+    )?;
+    move_voting_power(
+        deps.storage,
+        env.block.height,
+        Some(&owner_addr),
+        Some(&rcpt_addr),
+        received,
     )?;
 
     let res = Response::new().add_attributes(vec![
@@ -141,6 +258,7 @@ pub fn execute_transfer_from(
         attr("to", recipient),
         attr("by", info.sender),
         attr("amount", amount),
+        attr("fee", fee),
     ]);
     Ok(res)
 }
@@ -161,6 +279,9 @@ pub fn execute_burn_from(
 
     let owner_addr = deps.api.addr_validate(&owner)?;
 
+    assert_not_paused(deps.storage, &info.sender)?;
+    assert_not_frozen(deps.storage, &owner_addr)?;
+
     // deduct allowance before doing anything else have enough allowance
     deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
 
@@ -168,15 +289,18 @@ pub fn execute_burn_from(
     BALANCES.update(
         deps.storage,
         &owner_addr,
+        env.block.height,
         |balance: Option<Uint128>| -> StdResult<_> {
             Ok(balance.unwrap_or_default().checked_sub(amount)?)
         },
     )?;
+    move_voting_power(deps.storage, env.block.height, Some(&owner_addr), None, amount)?;
     // reduce total_supply
-    TOKEN_INFO.update(deps.storage, |mut meta| -> StdResult<_> {
+    let meta = TOKEN_INFO.update(deps.storage, |mut meta| -> StdResult<_> {
         meta.total_supply = meta.total_supply.checked_sub(amount)?;
         Ok(meta)
     })?;
+    TOTAL_SUPPLY_HISTORY.save(deps.storage, &meta.total_supply, env.block.height)?;
 
     let res = Response::new().add_attributes(vec![
         attr("action", "burn_from"),
@@ -199,6 +323,11 @@ pub fn execute_send_from(
     let rcpt_addr = deps.api.addr_validate(&contract)?;
     let owner_addr = deps.api.addr_validate(&owner)?;
 
+    assert_not_paused(deps.storage, &info.sender)?;
+    assert_not_frozen(deps.storage, &owner_addr)?;
+    assert_not_frozen(deps.storage, &info.sender)?;
+    assert_can_receive(deps.storage, &rcpt_addr)?;
+
     // deduct allowance before doing anything else have enough allowance
     deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
 
@@ -206,14 +335,25 @@ pub fn execute_send_from(
     BALANCES.update(
         deps.storage,
         &owner_addr,
+        env.block.height,
         |balance: Option<Uint128>| -> StdResult<_> {
             Ok(balance.unwrap_or_default().checked_sub(amount)?)
         },
     )?;
+    let fee = charge_transfer_fee(deps.storage, env.block.height, &owner_addr, &rcpt_addr, amount)?;
+    let received = amount.checked_sub(fee)?;
     BALANCES.update(
         deps.storage,
         &rcpt_addr,
-        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
+        env.block.height,
+        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + received) },
+    )?;
+    move_voting_power(
+        deps.storage,
+        env.block.height,
+        Some(&owner_addr),
+        Some(&rcpt_addr),
+        received,
     )?;
 
     let attrs = vec![
@@ -222,12 +362,13 @@ pub fn execute_send_from(
         attr("to", &contract),
         attr("by", &info.sender),
         attr("amount", amount),
+        attr("fee", fee),
     ];
 
     // create a send message
     let msg = Cw20ReceiveMsg {
         sender: info.sender.into(),
-        amount,
+        amount: received,
         msg,
     }
         .into_cosmos_msg(contract)?;
@@ -245,6 +386,14 @@ pub fn query_allowance(deps: Deps, owner: String, spender: String) -> StdResult<
     Ok(allowance)
 }
 
+pub fn query_permit_nonce(deps: Deps, owner: String) -> StdResult<PermitNonceResponse> {
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let nonce = PERMIT_NONCES
+        .may_load(deps.storage, &owner_addr)?
+        .unwrap_or_default();
+    Ok(PermitNonceResponse { nonce })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -252,6 +401,8 @@ mod tests {
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
     use cosmwasm_std::{coins, CosmosMsg, SubMsg, Timestamp, WasmMsg};
     use cw20::{Cw20Coin, TokenInfoResponse};
+    use k256::ecdsa::signature::Signer;
+    use k256::ecdsa::{Signature, SigningKey};
 
     use crate::contract::{execute, instantiate, query_balance, query_token_info};
     use crate::msg::{ExecuteMsg, InstantiateMsg};
@@ -275,6 +426,7 @@ mod tests {
                 address: addr.into(),
                 amount,
             }],
+            mint: None,
             marketing: None,
         };
         let info = mock_info("creator", &[]);
@@ -654,6 +806,177 @@ mod tests {
         assert_eq!(err, ContractError::Expired {});
     }
 
+    #[test]
+    fn pause_blocks_allowance_spending() {
+        let mut deps = mock_dependencies(&[]);
+        let owner = String::from("addr0001");
+        let spender = String::from("addr0002");
+        let rcpt = String::from("addr0003");
+
+        let start = Uint128::new(999999);
+        do_instantiate(deps.as_mut(), &owner, start);
+
+        // provide an allowance
+        let allow1 = Uint128::new(77777);
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: spender.clone(),
+            amount: allow1,
+            expires: None,
+        };
+        let info = mock_info(owner.as_ref(), &[]);
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        // owner pauses the contract
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Pause {}).unwrap();
+
+        let msg = ExecuteMsg::TransferFrom {
+            owner: owner.clone(),
+            recipient: rcpt.clone(),
+            amount: Uint128::new(44444),
+        };
+        let info = mock_info(spender.as_ref(), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Paused {});
+
+        let msg = ExecuteMsg::SendFrom {
+            owner: owner.clone(),
+            contract: rcpt,
+            amount: Uint128::new(44444),
+            msg: Binary::default(),
+        };
+        let info = mock_info(spender.as_ref(), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Paused {});
+
+        // allowance stays untouched
+        let allowance = query_allowance(deps.as_ref(), owner, spender).unwrap();
+        assert_eq!(allowance.allowance, allow1);
+    }
+
+    // signs the permit the same way a wallet would: secp256k1 over sha256 of the payload
+    fn sign_permit(key: &SigningKey, env: &Env, msg: &ExecuteMsg) -> (Binary, Binary) {
+        let payload = match msg {
+            ExecuteMsg::Permit {
+                owner,
+                spender,
+                amount,
+                expires,
+                nonce,
+                ..
+            } => PermitPayload {
+                chain_id: env.block.chain_id.clone(),
+                contract: env.contract.address.to_string(),
+                owner: owner.clone(),
+                spender: spender.clone(),
+                amount: *amount,
+                expires: *expires,
+                nonce: *nonce,
+            },
+            _ => panic!("not a permit"),
+        };
+        let signature: Signature = key.sign(&to_vec(&payload).unwrap());
+        let pubkey = key.verifying_key().to_bytes();
+        (
+            Binary::from(signature.as_ref()),
+            Binary::from(pubkey.as_slice()),
+        )
+    }
+
+    fn permit_msg(owner: &str, spender: &str, amount: u128, nonce: u64) -> ExecuteMsg {
+        ExecuteMsg::Permit {
+            owner: owner.to_string(),
+            spender: spender.to_string(),
+            amount: Uint128::new(amount),
+            expires: None,
+            nonce,
+            signature: Binary::default(),
+            pubkey: Binary::default(),
+        }
+    }
+
+    fn with_signature(msg: ExecuteMsg, sig: (Binary, Binary)) -> ExecuteMsg {
+        match msg {
+            ExecuteMsg::Permit {
+                owner,
+                spender,
+                amount,
+                expires,
+                nonce,
+                ..
+            } => ExecuteMsg::Permit {
+                owner,
+                spender,
+                amount,
+                expires,
+                nonce,
+                signature: sig.0,
+                pubkey: sig.1,
+            },
+            _ => panic!("not a permit"),
+        }
+    }
+
+    #[test]
+    fn permit_grants_allowance() {
+        let mut deps = mock_dependencies(&[]);
+        let env = mock_env();
+
+        let key = SigningKey::from_bytes(&[7u8; 32]).unwrap();
+        let owner = pubkey_to_address(
+            "terra1dnf8xxhal8rc9vul43a3v3lsu79uym68znyk3q",
+            key.verifying_key().to_bytes().as_slice(),
+        )
+            .unwrap();
+        let spender = String::from("addr0002");
+        let rcpt = String::from("addr0003");
+        do_instantiate(deps.as_mut(), &owner, Uint128::new(12340000));
+
+        assert_eq!(
+            query_permit_nonce(deps.as_ref(), owner.clone()).unwrap(),
+            PermitNonceResponse { nonce: 0 }
+        );
+
+        // anyone can submit the permit, spender uses it in the same transaction
+        let msg = permit_msg(&owner, &spender, 7777, 0);
+        let sig = sign_permit(&key, &env, &msg);
+        let msg = with_signature(msg, sig);
+        execute(deps.as_mut(), env.clone(), mock_info(&spender, &[]), msg.clone()).unwrap();
+
+        let allowance = query_allowance(deps.as_ref(), owner.clone(), spender.clone()).unwrap();
+        assert_eq!(allowance.allowance, Uint128::new(7777));
+        assert_eq!(
+            query_permit_nonce(deps.as_ref(), owner.clone()).unwrap(),
+            PermitNonceResponse { nonce: 1 }
+        );
+
+        let transfer = ExecuteMsg::TransferFrom {
+            owner: owner.clone(),
+            recipient: rcpt.clone(),
+            amount: Uint128::new(7777),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info(&spender, &[]), transfer).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), rcpt), Uint128::new(7777));
+
+        // permit cannot be replayed
+        let err = execute(deps.as_mut(), env.clone(), mock_info(&spender, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidPermitNonce { expected: 1 });
+
+        // permit signed by a different key is rejected
+        let other_key = SigningKey::from_bytes(&[8u8; 32]).unwrap();
+        let msg = permit_msg(&owner, &spender, 1_000_000, 1);
+        let sig = sign_permit(&other_key, &env, &msg);
+        let msg = with_signature(msg, sig);
+        let err = execute(deps.as_mut(), env.clone(), mock_info(&spender, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidPermitSignature {});
+
+        // tampered amount is rejected
+        let msg = permit_msg(&owner, &spender, 1, 1);
+        let sig = sign_permit(&key, &env, &msg);
+        let msg = with_signature(permit_msg(&owner, &spender, 1_000_000, 1), sig);
+        let err = execute(deps.as_mut(), env, mock_info(&spender, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidPermitSignature {});
+    }
+
     #[test]
     fn other_users_cannot_burn() {
         let mut deps = mock_dependencies(&[]);
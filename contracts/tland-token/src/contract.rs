@@ -1,21 +1,35 @@
-use cosmwasm_std::{BankMsg, Binary, coin, Coin, Decimal, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, SubMsg, to_binary, Uint128};
+use cosmwasm_std::{Addr, BankMsg, Binary, coin, Coin, Decimal, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Storage, SubMsg, to_binary, Uint128};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cw2::{get_contract_version, set_contract_version};
+use cw2::set_contract_version;
 use cw20::{
     BalanceResponse, Cw20Coin, Cw20ReceiveMsg, DownloadLogoResponse, EmbeddedLogo, Logo, LogoInfo,
-    MarketingInfoResponse, TokenInfoResponse,
+    MarketingInfoResponse, MinterResponse, TokenInfoResponse,
+};
+use ownership::{
+    accept_ownership, cancel_ownership_proposal, check_migration, propose_new_owner,
+    query_pending_owner,
 };
 use terra_cosmwasm::TerraQuerier;
 
 use crate::allowances::{
-    execute_burn_from, execute_decrease_allowance, execute_increase_allowance, execute_send_from,
-    execute_transfer_from, query_allowance,
+    execute_burn_from, execute_decrease_allowance, execute_increase_allowance, execute_permit,
+    execute_send_from, execute_transfer_from, query_allowance, query_permit_nonce,
+};
+use crate::enumerable::{
+    query_all_accounts, query_all_allowances, query_all_spender_allowances, query_frozen_accounts,
 };
-use crate::enumerable::{query_all_accounts, query_all_allowances};
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
-use crate::state::{BALANCES, CONFIG, Config, LOGO, MARKETING_INFO, TOKEN_INFO, TokenInfo};
+use crate::migrations::migrate_from;
+use crate::msg::{
+    BatchSendItem, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, SimulateTransferResponse,
+    TotalSupplyResponse, TransferFeeInfo,
+};
+use crate::state::{
+    BALANCES, CONFIG, Config, FROZEN, LOGO, MARKETING_INFO, MinterData, TOKEN_INFO, TokenInfo,
+    TOTAL_SUPPLY_HISTORY, TransferFee,
+};
+use crate::voting::{execute_delegate, move_voting_power, query_delegates, query_voting_power};
 
 static DECIMAL_FRACTION: Uint128 = Uint128::new(1_000_000_000_000_000_000u128);
 
@@ -80,8 +94,8 @@ fn verify_png_logo(logo: &[u8]) -> Result<(), ContractError> {
 /// Checks if passed logo is correct, and if not, returns an error
 fn verify_logo(logo: &Logo) -> Result<(), ContractError> {
     match logo {
-        Logo::Embedded(EmbeddedLogo::Svg(logo)) => verify_xml_logo(&logo),
-        Logo::Embedded(EmbeddedLogo::Png(logo)) => verify_png_logo(&logo),
+        Logo::Embedded(EmbeddedLogo::Svg(logo)) => verify_xml_logo(logo),
+        Logo::Embedded(EmbeddedLogo::Png(logo)) => verify_png_logo(logo),
         Logo::Url(_) => Ok(()), // Any reasonable url validation would be regex based, probably not worth it
     }
 }
@@ -89,7 +103,7 @@ fn verify_logo(logo: &Logo) -> Result<(), ContractError> {
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     mut deps: DepsMut,
-    _env: Env,
+    env: Env,
     _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
@@ -97,7 +111,21 @@ pub fn instantiate(
     // check valid token info
     msg.validate()?;
     // create initial accounts
-    let total_supply = create_accounts(&mut deps, &msg.initial_balances)?;
+    let total_supply = create_accounts(&mut deps, &env, &msg.initial_balances)?;
+
+    if let Some(limit) = msg.get_cap() {
+        if total_supply > limit {
+            return Err(StdError::generic_err("Initial supply greater than cap").into());
+        }
+    }
+
+    let mint = match msg.mint {
+        Some(m) => Some(MinterData {
+            minter: deps.api.addr_validate(&m.minter)?,
+            cap: m.cap,
+        }),
+        None => None,
+    };
 
     // store token info
     let data = TokenInfo {
@@ -105,10 +133,18 @@ pub fn instantiate(
         symbol: msg.symbol,
         decimals: msg.decimals,
         total_supply,
+        mint,
     };
     TOKEN_INFO.save(deps.storage, &data)?;
-
-    let cfg = Config { owner: deps.api.addr_validate(&msg.owner)? };
+    TOTAL_SUPPLY_HISTORY.save(deps.storage, &total_supply, env.block.height)?;
+
+    let cfg = Config {
+        owner: deps.api.addr_validate(&msg.owner)?,
+        paused: false,
+        pause_allowlist: vec![],
+        frozen_can_receive: false,
+        transfer_fee: None,
+    };
     CONFIG.save(deps.storage, &cfg)?;
 
     if let Some(marketing) = msg.marketing {
@@ -140,21 +176,26 @@ pub fn instantiate(
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
-    let version = get_contract_version(deps.storage)?;
-    if version.contract != CONTRACT_NAME {
-        return Err(ContractError::CannotMigrate {
-            previous_contract: version.contract,
-        });
-    }
-    Ok(Response::default())
+pub fn migrate(mut deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let previous = check_migration(deps.storage, &[CONTRACT_NAME], CONTRACT_VERSION)?;
+    migrate_from(deps.branch(), &env, &previous.version)?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("previous_version", previous.version.to_string())
+        .add_attribute("new_version", CONTRACT_VERSION))
 }
 
-pub fn create_accounts(deps: &mut DepsMut, accounts: &[Cw20Coin]) -> StdResult<Uint128> {
+pub fn create_accounts(
+    deps: &mut DepsMut,
+    env: &Env,
+    accounts: &[Cw20Coin],
+) -> StdResult<Uint128> {
     let mut total_supply = Uint128::zero();
     for row in accounts {
         let address = deps.api.addr_validate(&row.address)?;
-        BALANCES.save(deps.storage, &address, &row.amount)?;
+        BALANCES.save(deps.storage, &address, &row.amount, env.block.height)?;
         total_supply += row.amount;
     }
     Ok(total_supply)
@@ -168,8 +209,29 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::UpdateConfig { owner } =>
-            execute_update_config(deps, env, info, owner),
+        ExecuteMsg::UpdateConfig { pause_allowlist, frozen_can_receive } =>
+            execute_update_config(deps, env, info, pause_allowlist, frozen_can_receive),
+        ExecuteMsg::ProposeNewOwner { owner, expiry } => {
+            let cfg = CONFIG.load(deps.storage)?;
+            Ok(propose_new_owner(deps, env, info, &cfg.owner, owner, expiry)?)
+        }
+        ExecuteMsg::AcceptOwnership {} => execute_accept_ownership(deps, env, info),
+        ExecuteMsg::CancelOwnershipProposal {} => {
+            let cfg = CONFIG.load(deps.storage)?;
+            Ok(cancel_ownership_proposal(deps, info, &cfg.owner)?)
+        }
+        ExecuteMsg::Pause {} => execute_set_paused(deps, env, info, true),
+        ExecuteMsg::Unpause {} => execute_set_paused(deps, env, info, false),
+        ExecuteMsg::Delegate { delegatee } => execute_delegate(deps, env, info, delegatee),
+        ExecuteMsg::UpdateTransferFee { transfer_fee } => {
+            execute_update_transfer_fee(deps, env, info, transfer_fee)
+        }
+        ExecuteMsg::FreezeAccount { address, reason } => {
+            execute_freeze_account(deps, env, info, address, reason)
+        }
+        ExecuteMsg::UnfreezeAccount { address } => {
+            execute_unfreeze_account(deps, env, info, address)
+        }
         ExecuteMsg::Transfer { recipient, amount } => {
             execute_transfer(deps, env, info, recipient, amount)
         }
@@ -179,6 +241,14 @@ pub fn execute(
             amount,
             msg,
         } => execute_send(deps, env, info, contract, amount, msg),
+        ExecuteMsg::BatchTransfer { transfers } => {
+            execute_batch_transfer(deps, env, info, transfers)
+        }
+        ExecuteMsg::BatchSend { sends } => execute_batch_send(deps, env, info, sends),
+        ExecuteMsg::Mint { recipient, amount } => execute_mint(deps, env, info, recipient, amount),
+        ExecuteMsg::UpdateMinter { minter, cap } => {
+            execute_update_minter(deps, env, info, minter, cap)
+        }
         ExecuteMsg::IncreaseAllowance {
             spender,
             amount,
@@ -189,6 +259,15 @@ pub fn execute(
             amount,
             expires,
         } => execute_decrease_allowance(deps, env, info, spender, amount, expires),
+        ExecuteMsg::Permit {
+            owner,
+            spender,
+            amount,
+            expires,
+            nonce,
+            signature,
+            pubkey,
+        } => execute_permit(deps, env, owner, spender, amount, expires, nonce, signature, pubkey),
         ExecuteMsg::TransferFrom {
             owner,
             recipient,
@@ -219,7 +298,8 @@ pub fn execute_update_config(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    new_owner: Option<String>,
+    new_pause_allowlist: Option<Vec<String>>,
+    new_frozen_can_receive: Option<bool>,
 ) -> Result<Response, ContractError> {
     // authorized owner
     let cfg = CONFIG.load(deps.storage)?;
@@ -230,9 +310,14 @@ pub fn execute_update_config(
     let api = deps.api;
 
     CONFIG.update(deps.storage, |mut existing_config| -> StdResult<_> {
-        // update new owner if set
-        if let Some(addr) = new_owner {
-            existing_config.owner = api.addr_validate(&addr)?;
+        if let Some(allowlist) = new_pause_allowlist {
+            existing_config.pause_allowlist = allowlist
+                .iter()
+                .map(|addr| api.addr_validate(addr))
+                .collect::<StdResult<Vec<_>>>()?;
+        }
+        if let Some(frozen_can_receive) = new_frozen_can_receive {
+            existing_config.frozen_can_receive = frozen_can_receive;
         }
         Ok(existing_config)
     })?;
@@ -242,6 +327,195 @@ pub fn execute_update_config(
         .add_attribute("sender", info.sender))
 }
 
+pub fn execute_accept_ownership(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let new_owner = accept_ownership(deps.storage, &env, &info.sender)?;
+    CONFIG.update(deps.storage, |mut existing_config| -> StdResult<_> {
+        existing_config.owner = new_owner;
+        Ok(existing_config)
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "accept_ownership")
+        .add_attribute("owner", info.sender))
+}
+
+pub fn execute_set_paused(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    // authorized owner
+    let mut cfg = CONFIG.load(deps.storage)?;
+    if info.sender != cfg.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    cfg.paused = paused;
+    CONFIG.save(deps.storage, &cfg)?;
+
+    Ok(Response::new()
+        .add_attribute("action", if paused { "pause" } else { "unpause" })
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_update_transfer_fee(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    transfer_fee: Option<TransferFeeInfo>,
+) -> Result<Response, ContractError> {
+    // authorized owner
+    let mut cfg = CONFIG.load(deps.storage)?;
+    if info.sender != cfg.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    cfg.transfer_fee = match transfer_fee {
+        Some(fee) => {
+            if fee.rate_bps > 10_000 || matches!(fee.max_fee, Some(max) if max < fee.min_fee) {
+                return Err(ContractError::InvalidTransferFee {});
+            }
+            Some(TransferFee {
+                rate_bps: fee.rate_bps,
+                min_fee: fee.min_fee,
+                max_fee: fee.max_fee,
+                treasury: fee
+                    .treasury
+                    .map(|addr| deps.api.addr_validate(&addr))
+                    .transpose()?,
+                exempt: fee
+                    .exempt
+                    .iter()
+                    .map(|addr| deps.api.addr_validate(addr))
+                    .collect::<StdResult<Vec<_>>>()?,
+            })
+        }
+        None => None,
+    };
+    CONFIG.save(deps.storage, &cfg)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_transfer_fee")
+        .add_attribute("sender", info.sender))
+}
+
+/// Charges the fee for moving amount between the accounts. The fee is credited to the
+/// treasury or burned. Returns the charged fee.
+pub fn charge_transfer_fee(
+    storage: &mut dyn Storage,
+    height: u64,
+    from: &Addr,
+    to: &Addr,
+    amount: Uint128,
+) -> Result<Uint128, ContractError> {
+    let transfer_fee = match CONFIG.load(storage)?.transfer_fee {
+        Some(transfer_fee) => transfer_fee,
+        None => return Ok(Uint128::zero()),
+    };
+
+    let fee = transfer_fee.compute(from, to, amount);
+    if fee.is_zero() {
+        return Ok(fee);
+    }
+
+    match transfer_fee.treasury {
+        Some(treasury) => {
+            assert_can_receive(storage, &treasury)?;
+            BALANCES.update(
+                storage,
+                &treasury,
+                height,
+                |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + fee) },
+            )?;
+            move_voting_power(storage, height, Some(from), Some(&treasury), fee)?;
+        }
+        None => {
+            move_voting_power(storage, height, Some(from), None, fee)?;
+            let token_info = TOKEN_INFO.update(storage, |mut info| -> StdResult<_> {
+                info.total_supply = info.total_supply.checked_sub(fee)?;
+                Ok(info)
+            })?;
+            TOTAL_SUPPLY_HISTORY.save(storage, &token_info.total_supply, height)?;
+        }
+    }
+    Ok(fee)
+}
+
+pub fn execute_freeze_account(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    address: String,
+    reason: String,
+) -> Result<Response, ContractError> {
+    // authorized owner
+    let cfg = CONFIG.load(deps.storage)?;
+    if info.sender != cfg.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let addr = deps.api.addr_validate(&address)?;
+    FROZEN.save(deps.storage, &addr, &reason)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "freeze_account")
+        .add_attribute("address", address)
+        .add_attribute("reason", reason))
+}
+
+pub fn execute_unfreeze_account(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    // authorized owner
+    let cfg = CONFIG.load(deps.storage)?;
+    if info.sender != cfg.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let addr = deps.api.addr_validate(&address)?;
+    FROZEN.remove(deps.storage, &addr);
+
+    Ok(Response::new()
+        .add_attribute("action", "unfreeze_account")
+        .add_attribute("address", address))
+}
+
+/// Fails with `AccountFrozen` if the account is on the frozen list
+pub fn assert_not_frozen(storage: &dyn Storage, addr: &Addr) -> Result<(), ContractError> {
+    if FROZEN.has(storage, addr) {
+        return Err(ContractError::AccountFrozen {
+            address: addr.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Fails with `AccountFrozen` if the account is frozen and frozen accounts cannot receive tokens
+pub fn assert_can_receive(storage: &dyn Storage, addr: &Addr) -> Result<(), ContractError> {
+    let cfg = CONFIG.load(storage)?;
+    if cfg.frozen_can_receive {
+        return Ok(());
+    }
+    assert_not_frozen(storage, addr)
+}
+
+/// Fails with `Paused` if the contract is paused and sender is not on the allowlist
+pub fn assert_not_paused(storage: &dyn Storage, sender: &Addr) -> Result<(), ContractError> {
+    let cfg = CONFIG.load(storage)?;
+    if !cfg.can_move_funds(sender) {
+        return Err(ContractError::Paused {});
+    }
+    Ok(())
+}
+
 pub fn execute_withdraw_locked_funds(
     deps: DepsMut,
     info: MessageInfo,
@@ -269,7 +543,7 @@ pub fn execute_withdraw_locked_funds(
 
 pub fn execute_transfer(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     recipient: String,
     amount: Uint128,
@@ -278,32 +552,48 @@ pub fn execute_transfer(
         return Err(ContractError::InvalidZeroAmount {});
     }
 
+    assert_not_paused(deps.storage, &info.sender)?;
+    assert_not_frozen(deps.storage, &info.sender)?;
+
     let rcpt_addr = deps.api.addr_validate(&recipient)?;
+    assert_can_receive(deps.storage, &rcpt_addr)?;
 
     BALANCES.update(
         deps.storage,
         &info.sender,
+        env.block.height,
         |balance: Option<Uint128>| -> StdResult<_> {
             Ok(balance.unwrap_or_default().checked_sub(amount)?)
         },
     )?;
+    let fee = charge_transfer_fee(deps.storage, env.block.height, &info.sender, &rcpt_addr, amount)?;
+    let received = amount.checked_sub(fee)?;
     BALANCES.update(
         deps.storage,
         &rcpt_addr,
-        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
+        env.block.height,
+        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + received) },
+    )?;
+    move_voting_power(
+        deps.storage,
+        env.block.height,
+        Some(&info.sender),
+        Some(&rcpt_addr),
+        received,
     )?;
 
     let res = Response::new()
         .add_attribute("action", "transfer")
         .add_attribute("from", info.sender)
         .add_attribute("to", recipient)
-        .add_attribute("amount", amount);
+        .add_attribute("amount", amount)
+        .add_attribute("fee", fee);
     Ok(res)
 }
 
 pub fn execute_burn(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     amount: Uint128,
 ) -> Result<Response, ContractError> {
@@ -317,19 +607,24 @@ pub fn execute_burn(
         return Err(ContractError::InvalidZeroAmount {});
     }
 
+    assert_not_paused(deps.storage, &info.sender)?;
+
     // lower balance
     BALANCES.update(
         deps.storage,
         &info.sender,
+        env.block.height,
         |balance: Option<Uint128>| -> StdResult<_> {
             Ok(balance.unwrap_or_default().checked_sub(amount)?)
         },
     )?;
+    move_voting_power(deps.storage, env.block.height, Some(&info.sender), None, amount)?;
     // reduce total_supply
-    TOKEN_INFO.update(deps.storage, |mut info| -> StdResult<_> {
+    let token_info = TOKEN_INFO.update(deps.storage, |mut info| -> StdResult<_> {
         info.total_supply = info.total_supply.checked_sub(amount)?;
         Ok(info)
     })?;
+    TOTAL_SUPPLY_HISTORY.save(deps.storage, &token_info.total_supply, env.block.height)?;
 
     let res = Response::new()
         .add_attribute("action", "burn")
@@ -338,10 +633,95 @@ pub fn execute_burn(
     Ok(res)
 }
 
-pub fn execute_send(
+pub fn execute_mint(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    if amount == Uint128::zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let mut config = TOKEN_INFO.load(deps.storage)?;
+    if config.mint.is_none() || config.mint.as_ref().unwrap().minter != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // update supply and enforce cap
+    config.total_supply += amount;
+    if let Some(limit) = config.get_cap() {
+        if config.total_supply > limit {
+            return Err(ContractError::CannotExceedCap {});
+        }
+    }
+    TOKEN_INFO.save(deps.storage, &config)?;
+    TOTAL_SUPPLY_HISTORY.save(deps.storage, &config.total_supply, env.block.height)?;
+
+    // add amount to recipient balance
+    let rcpt_addr = deps.api.addr_validate(&recipient)?;
+    assert_can_receive(deps.storage, &rcpt_addr)?;
+    BALANCES.update(
+        deps.storage,
+        &rcpt_addr,
+        env.block.height,
+        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
+    )?;
+    move_voting_power(deps.storage, env.block.height, None, Some(&rcpt_addr), amount)?;
+
+    let res = Response::new()
+        .add_attribute("action", "mint")
+        .add_attribute("to", recipient)
+        .add_attribute("amount", amount);
+    Ok(res)
+}
+
+pub fn execute_update_minter(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
+    new_minter: Option<String>,
+    new_cap: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    // authorized owner
+    let cfg = CONFIG.load(deps.storage)?;
+    if info.sender != cfg.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut token_info = TOKEN_INFO.load(deps.storage)?;
+    // already minted tokens have to fit under the new cap
+    if matches!(new_cap, Some(cap) if cap < token_info.total_supply) {
+        return Err(ContractError::CapBelowTotalSupply {});
+    }
+
+    let mint = match new_minter {
+        Some(addr) => Some(MinterData {
+            minter: deps.api.addr_validate(&addr)?,
+            cap: new_cap,
+        }),
+        None => None,
+    };
+
+    token_info.mint = mint.clone();
+    TOKEN_INFO.save(deps.storage, &token_info)?;
+
+    let res = Response::new()
+        .add_attribute("action", "update_minter")
+        .add_attribute(
+            "new_minter",
+            mint.as_ref()
+                .map(|m| m.minter.to_string())
+                .unwrap_or_else(|| "None".to_string()),
+        );
+    Ok(res)
+}
+
+pub fn execute_send(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
     contract: String,
     amount: Uint128,
     msg: Binary,
@@ -350,20 +730,35 @@ pub fn execute_send(
         return Err(ContractError::InvalidZeroAmount {});
     }
 
+    assert_not_paused(deps.storage, &info.sender)?;
+    assert_not_frozen(deps.storage, &info.sender)?;
+
     let rcpt_addr = deps.api.addr_validate(&contract)?;
+    assert_can_receive(deps.storage, &rcpt_addr)?;
 
     // move the tokens to the contract
     BALANCES.update(
         deps.storage,
         &info.sender,
+        env.block.height,
         |balance: Option<Uint128>| -> StdResult<_> {
             Ok(balance.unwrap_or_default().checked_sub(amount)?)
         },
     )?;
+    let fee = charge_transfer_fee(deps.storage, env.block.height, &info.sender, &rcpt_addr, amount)?;
+    let received = amount.checked_sub(fee)?;
     BALANCES.update(
         deps.storage,
         &rcpt_addr,
-        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
+        env.block.height,
+        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + received) },
+    )?;
+    move_voting_power(
+        deps.storage,
+        env.block.height,
+        Some(&info.sender),
+        Some(&rcpt_addr),
+        received,
     )?;
 
     let res = Response::new()
@@ -371,10 +766,11 @@ pub fn execute_send(
         .add_attribute("from", &info.sender)
         .add_attribute("to", &contract)
         .add_attribute("amount", amount)
+        .add_attribute("fee", fee)
         .add_message(
             Cw20ReceiveMsg {
                 sender: info.sender.into(),
-                amount,
+                amount: received,
                 msg,
             }
                 .into_cosmos_msg(contract)?,
@@ -382,6 +778,122 @@ pub fn execute_send(
     Ok(res)
 }
 
+pub fn execute_batch_transfer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    transfers: Vec<Cw20Coin>,
+) -> Result<Response, ContractError> {
+    let legs = transfers
+        .into_iter()
+        .map(|t| (t.address, t.amount))
+        .collect::<Vec<_>>();
+    let (total, fees) = move_batch(deps, &env, &info.sender, &legs)?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "batch_transfer")
+        .add_attribute("from", info.sender)
+        .add_attribute("total", total);
+    for ((recipient, amount), fee) in legs.into_iter().zip(fees) {
+        res = res
+            .add_attribute("to", recipient)
+            .add_attribute("amount", amount)
+            .add_attribute("fee", fee);
+    }
+    Ok(res)
+}
+
+pub fn execute_batch_send(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sends: Vec<BatchSendItem>,
+) -> Result<Response, ContractError> {
+    let legs = sends
+        .iter()
+        .map(|s| (s.contract.clone(), s.amount))
+        .collect::<Vec<_>>();
+    let (total, fees) = move_batch(deps, &env, &info.sender, &legs)?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "batch_send")
+        .add_attribute("from", &info.sender)
+        .add_attribute("total", total);
+    for (send, fee) in sends.into_iter().zip(fees) {
+        res = res
+            .add_attribute("to", &send.contract)
+            .add_attribute("amount", send.amount)
+            .add_attribute("fee", fee)
+            .add_message(
+                Cw20ReceiveMsg {
+                    sender: info.sender.to_string(),
+                    amount: send.amount.checked_sub(fee)?,
+                    msg: send.msg,
+                }
+                    .into_cosmos_msg(send.contract)?,
+            );
+    }
+    Ok(res)
+}
+
+/// Debits the sender once with the sum of all legs and credits every recipient.
+/// Returns the total amount moved and the transfer fee charged on every leg.
+fn move_batch(
+    deps: DepsMut,
+    env: &Env,
+    sender: &Addr,
+    legs: &[(String, Uint128)],
+) -> Result<(Uint128, Vec<Uint128>), ContractError> {
+    assert_not_paused(deps.storage, sender)?;
+    assert_not_frozen(deps.storage, sender)?;
+
+    // validate all legs before touching any balance
+    let mut total = Uint128::zero();
+    let mut recipients = Vec::with_capacity(legs.len());
+    for (recipient, amount) in legs {
+        if amount.is_zero() {
+            return Err(ContractError::InvalidZeroAmount {});
+        }
+        let rcpt_addr = deps.api.addr_validate(recipient)?;
+        assert_can_receive(deps.storage, &rcpt_addr)?;
+        recipients.push((rcpt_addr, *amount));
+        total = total.checked_add(*amount)?;
+    }
+    if total.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    BALANCES.update(
+        deps.storage,
+        sender,
+        env.block.height,
+        |balance: Option<Uint128>| -> StdResult<_> {
+            Ok(balance.unwrap_or_default().checked_sub(total)?)
+        },
+    )?;
+    let mut fees = Vec::with_capacity(recipients.len());
+    for (rcpt_addr, amount) in recipients {
+        let fee = charge_transfer_fee(deps.storage, env.block.height, sender, &rcpt_addr, amount)?;
+        let received = amount.checked_sub(fee)?;
+        BALANCES.update(
+            deps.storage,
+            &rcpt_addr,
+            env.block.height,
+            |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + received) },
+        )?;
+        move_voting_power(
+            deps.storage,
+            env.block.height,
+            Some(sender),
+            Some(&rcpt_addr),
+            received,
+        )?;
+        fees.push(fee);
+    }
+
+    Ok((total, fees))
+}
+
 pub fn execute_update_marketing(
     deps: DepsMut,
     _env: Env,
@@ -471,29 +983,69 @@ pub fn execute_upload_logo(
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::PendingOwner {} => to_binary(&query_pending_owner(deps)?),
         QueryMsg::Balance { address } => to_binary(&query_balance(deps, address)?),
+        QueryMsg::BalanceAtHeight { address, height } => {
+            to_binary(&query_balance_at_height(deps, address, height)?)
+        }
+        QueryMsg::VotingPower { address, height } => {
+            to_binary(&query_voting_power(deps, address, height)?)
+        }
+        QueryMsg::Delegates { address } => to_binary(&query_delegates(deps, address)?),
+        QueryMsg::SimulateTransfer { from, to, amount } => {
+            to_binary(&query_simulate_transfer(deps, from, to, amount)?)
+        }
         QueryMsg::TokenInfo {} => to_binary(&query_token_info(deps)?),
+        QueryMsg::TotalSupplyAtHeight { height } => {
+            to_binary(&query_total_supply_at_height(deps, height)?)
+        }
+        QueryMsg::Minter {} => to_binary(&query_minter(deps)?),
         QueryMsg::Allowance { owner, spender } => {
             to_binary(&query_allowance(deps, owner, spender)?)
         }
+        QueryMsg::PermitNonce { owner } => to_binary(&query_permit_nonce(deps, owner)?),
         QueryMsg::AllAllowances {
             owner,
             start_after,
             limit,
-        } => to_binary(&query_all_allowances(deps, owner, start_after, limit)?),
+            exclude_expired,
+        } => to_binary(&query_all_allowances(
+            deps,
+            env,
+            owner,
+            start_after,
+            limit,
+            exclude_expired,
+        )?),
+        QueryMsg::AllSpenderAllowances {
+            spender,
+            start_after,
+            limit,
+            exclude_expired,
+        } => to_binary(&query_all_spender_allowances(
+            deps,
+            env,
+            spender,
+            start_after,
+            limit,
+            exclude_expired,
+        )?),
         QueryMsg::AllAccounts { start_after, limit } => {
             to_binary(&query_all_accounts(deps, start_after, limit)?)
         }
+        QueryMsg::ListFrozenAccounts { start_after, limit } => {
+            to_binary(&query_frozen_accounts(deps, start_after, limit)?)
+        }
         QueryMsg::MarketingInfo {} => to_binary(&query_marketing_info(deps)?),
         QueryMsg::DownloadLogo {} => to_binary(&query_download_logo(deps)?),
     }
 }
 
 pub fn query_config(deps: Deps) -> StdResult<Config> {
-    Ok(CONFIG.load(deps.storage)?)
+    CONFIG.load(deps.storage)
 }
 
 pub fn query_balance(deps: Deps, address: String) -> StdResult<BalanceResponse> {
@@ -504,15 +1056,67 @@ pub fn query_balance(deps: Deps, address: String) -> StdResult<BalanceResponse>
     Ok(BalanceResponse { balance })
 }
 
-pub fn query_token_info(deps: Deps) -> StdResult<TokenInfoResponse> {
-    let info = TOKEN_INFO.load(deps.storage)?;
-    let res = TokenInfoResponse {
-        name: info.name,
-        symbol: info.symbol,
-        decimals: info.decimals,
-        total_supply: info.total_supply,
-    };
-    Ok(res)
+pub fn query_balance_at_height(
+    deps: Deps,
+    address: String,
+    height: u64,
+) -> StdResult<BalanceResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let balance = BALANCES
+        .may_load_at_height(deps.storage, &address, height)?
+        .unwrap_or_default();
+    Ok(BalanceResponse { balance })
+}
+
+pub fn query_total_supply_at_height(
+    deps: Deps,
+    height: u64,
+) -> StdResult<TotalSupplyResponse> {
+    let total_supply = TOTAL_SUPPLY_HISTORY
+        .may_load_at_height(deps.storage, height)?
+        .unwrap_or_default();
+    Ok(TotalSupplyResponse { total_supply })
+}
+
+pub fn query_simulate_transfer(
+    deps: Deps,
+    from: String,
+    to: String,
+    amount: Uint128,
+) -> StdResult<SimulateTransferResponse> {
+    let from = deps.api.addr_validate(&from)?;
+    let to = deps.api.addr_validate(&to)?;
+    let fee = match CONFIG.load(deps.storage)?.transfer_fee {
+        Some(transfer_fee) => transfer_fee.compute(&from, &to, amount),
+        None => Uint128::zero(),
+    };
+    Ok(SimulateTransferResponse {
+        amount: amount.checked_sub(fee)?,
+        fee,
+    })
+}
+
+pub fn query_token_info(deps: Deps) -> StdResult<TokenInfoResponse> {
+    let info = TOKEN_INFO.load(deps.storage)?;
+    let res = TokenInfoResponse {
+        name: info.name,
+        symbol: info.symbol,
+        decimals: info.decimals,
+        total_supply: info.total_supply,
+    };
+    Ok(res)
+}
+
+pub fn query_minter(deps: Deps) -> StdResult<Option<MinterResponse>> {
+    let meta = TOKEN_INFO.load(deps.storage)?;
+    let minter = match meta.mint {
+        Some(m) => Some(MinterResponse {
+            minter: m.minter.into(),
+            cap: m.cap,
+        }),
+        None => None,
+    };
+    Ok(minter)
 }
 
 pub fn query_marketing_info(deps: Deps) -> StdResult<MarketingInfoResponse> {
@@ -563,10 +1167,11 @@ fn deduct_tax(deps: DepsMut, coin: Coin) -> Result<Coin, ContractError> {
 
 #[cfg(test)]
 mod tests {
-    use cosmwasm_std::{Addr, coins, CosmosMsg, from_binary, StdError, SubMsg, WasmMsg};
+    use cosmwasm_std::{Addr, attr, coins, CosmosMsg, from_binary, StdError, SubMsg, WasmMsg};
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
 
     use crate::msg::InstantiateMarketingInfo;
+    use ownership::{OwnershipError, PendingOwnerResponse};
 
     use super::*;
 
@@ -576,7 +1181,26 @@ mod tests {
 
     // this will set up the instantiation for other tests
     fn do_instantiate(deps: DepsMut, addr: &str, amount: Uint128) -> TokenInfoResponse {
-        _do_instantiate(deps, addr, amount)
+        _do_instantiate(deps, addr, amount, None)
+    }
+
+    // this will set up the instantiation for other tests
+    fn do_instantiate_with_minter(
+        deps: DepsMut,
+        addr: &str,
+        amount: Uint128,
+        minter: &str,
+        cap: Option<Uint128>,
+    ) -> TokenInfoResponse {
+        _do_instantiate(
+            deps,
+            addr,
+            amount,
+            Some(MinterResponse {
+                minter: minter.to_string(),
+                cap,
+            }),
+        )
     }
 
     // this will set up the instantiation for other tests
@@ -584,6 +1208,7 @@ mod tests {
         mut deps: DepsMut,
         addr: &str,
         amount: Uint128,
+        mint: Option<MinterResponse>,
     ) -> TokenInfoResponse {
         let instantiate_msg = InstantiateMsg {
             owner: "OWNER".to_string(),
@@ -594,6 +1219,7 @@ mod tests {
                 address: addr.to_string(),
                 amount,
             }],
+            mint: mint.clone(),
             marketing: None,
         };
         let info = mock_info("creator", &[]);
@@ -612,6 +1238,7 @@ mod tests {
             }
         );
         assert_eq!(get_balance(deps.as_ref(), addr), amount);
+        assert_eq!(query_minter(deps.as_ref()).unwrap(), mint);
         meta
     }
 
@@ -633,6 +1260,7 @@ mod tests {
                     address: String::from("addr0000"),
                     amount,
                 }],
+                mint: None,
                 marketing: None,
             };
             let info = mock_info("creator", &[]);
@@ -655,6 +1283,84 @@ mod tests {
             );
         }
 
+        #[test]
+        fn mintable() {
+            let mut deps = mock_dependencies(&[]);
+            let amount = Uint128::new(11223344);
+            let minter = String::from("asmodat");
+            let limit = Uint128::new(511223344);
+            let instantiate_msg = InstantiateMsg {
+                owner: "OWNER".to_string(),
+                name: "Cash Token".to_string(),
+                symbol: "CASH".to_string(),
+                decimals: 9,
+                initial_balances: vec![Cw20Coin {
+                    address: "addr0000".into(),
+                    amount,
+                }],
+                mint: Some(MinterResponse {
+                    minter: minter.clone(),
+                    cap: Some(limit),
+                }),
+                marketing: None,
+            };
+            let info = mock_info("creator", &[]);
+            let env = mock_env();
+            let res = instantiate(deps.as_mut(), env, info, instantiate_msg).unwrap();
+            assert_eq!(0, res.messages.len());
+
+            assert_eq!(
+                query_token_info(deps.as_ref()).unwrap(),
+                TokenInfoResponse {
+                    name: "Cash Token".to_string(),
+                    symbol: "CASH".to_string(),
+                    decimals: 9,
+                    total_supply: amount,
+                }
+            );
+            assert_eq!(
+                get_balance(deps.as_ref(), "addr0000"),
+                Uint128::new(11223344)
+            );
+            assert_eq!(
+                query_minter(deps.as_ref()).unwrap(),
+                Some(MinterResponse {
+                    minter,
+                    cap: Some(limit),
+                }),
+            );
+        }
+
+        #[test]
+        fn mintable_over_cap() {
+            let mut deps = mock_dependencies(&[]);
+            let amount = Uint128::new(11223344);
+            let minter = String::from("asmodat");
+            let limit = Uint128::new(11223300);
+            let instantiate_msg = InstantiateMsg {
+                owner: "OWNER".to_string(),
+                name: "Cash Token".to_string(),
+                symbol: "CASH".to_string(),
+                decimals: 9,
+                initial_balances: vec![Cw20Coin {
+                    address: String::from("addr0000"),
+                    amount,
+                }],
+                mint: Some(MinterResponse {
+                    minter,
+                    cap: Some(limit),
+                }),
+                marketing: None,
+            };
+            let info = mock_info("creator", &[]);
+            let env = mock_env();
+            let err = instantiate(deps.as_mut(), env, info, instantiate_msg).unwrap_err();
+            assert_eq!(
+                err,
+                StdError::generic_err("Initial supply greater than cap").into()
+            );
+        }
+
         mod marketing {
             use super::*;
 
@@ -667,6 +1373,7 @@ mod tests {
                     symbol: "CASH".to_string(),
                     decimals: 9,
                     initial_balances: vec![],
+                    mint: None,
                     marketing: Some(InstantiateMarketingInfo {
                         project: Some("Project".to_owned()),
                         description: Some("Description".to_owned()),
@@ -707,6 +1414,7 @@ mod tests {
                     symbol: "CASH".to_string(),
                     decimals: 9,
                     initial_balances: vec![],
+                    mint: None,
                     marketing: Some(InstantiateMarketingInfo {
                         project: Some("Project".to_owned()),
                         description: Some("Description".to_owned()),
@@ -729,6 +1437,160 @@ mod tests {
         }
     }
 
+    #[test]
+    fn can_mint_by_minter() {
+        let mut deps = mock_dependencies(&[]);
+
+        let genesis = String::from("genesis");
+        let amount = Uint128::new(11223344);
+        let minter = String::from("asmodat");
+        let limit = Uint128::new(511223344);
+        do_instantiate_with_minter(deps.as_mut(), &genesis, amount, &minter, Some(limit));
+
+        // minter can mint coins to some winner
+        let winner = String::from("lucky");
+        let prize = Uint128::new(222_222_222);
+        let msg = ExecuteMsg::Mint {
+            recipient: winner.clone(),
+            amount: prize,
+        };
+
+        let info = mock_info(minter.as_ref(), &[]);
+        let env = mock_env();
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+        assert_eq!(get_balance(deps.as_ref(), genesis), amount);
+        assert_eq!(get_balance(deps.as_ref(), winner.clone()), prize);
+
+        // but cannot mint nothing
+        let msg = ExecuteMsg::Mint {
+            recipient: winner.clone(),
+            amount: Uint128::zero(),
+        };
+        let info = mock_info(minter.as_ref(), &[]);
+        let env = mock_env();
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidZeroAmount {});
+
+        // but if it exceeds cap (even over multiple rounds), it fails
+        let msg = ExecuteMsg::Mint {
+            recipient: winner,
+            amount: Uint128::new(333_222_222),
+        };
+        let info = mock_info(minter.as_ref(), &[]);
+        let env = mock_env();
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(err, ContractError::CannotExceedCap {});
+    }
+
+    #[test]
+    fn others_cannot_mint() {
+        let mut deps = mock_dependencies(&[]);
+        do_instantiate_with_minter(
+            deps.as_mut(),
+            "genesis",
+            Uint128::new(1234),
+            "minter",
+            None,
+        );
+
+        let msg = ExecuteMsg::Mint {
+            recipient: String::from("lucky"),
+            amount: Uint128::new(222),
+        };
+        let info = mock_info("anyone else", &[]);
+        let env = mock_env();
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn no_one_mints_if_minter_unset() {
+        let mut deps = mock_dependencies(&[]);
+        do_instantiate(deps.as_mut(), "genesis", Uint128::new(1234));
+
+        let msg = ExecuteMsg::Mint {
+            recipient: String::from("lucky"),
+            amount: Uint128::new(222),
+        };
+        let info = mock_info("genesis", &[]);
+        let env = mock_env();
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn update_minter() {
+        let mut deps = mock_dependencies(&[]);
+        let minter = String::from("minter");
+        do_instantiate_with_minter(
+            deps.as_mut(),
+            "genesis",
+            Uint128::new(1234),
+            &minter,
+            None,
+        );
+
+        // cap cannot be set below the supply already minted
+        let msg = ExecuteMsg::UpdateMinter {
+            minter: Some(String::from("new_minter")),
+            cap: Some(Uint128::new(1233)),
+        };
+        let info = mock_info("OWNER", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::CapBelowTotalSupply {});
+
+        // only owner can change the minter
+        let msg = ExecuteMsg::UpdateMinter {
+            minter: Some(String::from("new_minter")),
+            cap: Some(Uint128::new(2000)),
+        };
+        let info = mock_info(minter.as_ref(), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let info = mock_info("OWNER", &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            query_minter(deps.as_ref()).unwrap(),
+            Some(MinterResponse {
+                minter: String::from("new_minter"),
+                cap: Some(Uint128::new(2000)),
+            })
+        );
+
+        // old minter lost the role, new one is bound by the new cap
+        let msg = ExecuteMsg::Mint {
+            recipient: String::from("lucky"),
+            amount: Uint128::new(766),
+        };
+        let info = mock_info(minter.as_ref(), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let info = mock_info("new_minter", &[]);
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        assert_eq!(
+            query_token_info(deps.as_ref()).unwrap().total_supply,
+            Uint128::new(2000)
+        );
+        let msg = ExecuteMsg::Mint {
+            recipient: String::from("lucky"),
+            amount: Uint128::new(1),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::CannotExceedCap {});
+
+        // minting can be disabled entirely
+        let msg = ExecuteMsg::UpdateMinter {
+            minter: None,
+            cap: None,
+        };
+        let info = mock_info("OWNER", &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(query_minter(deps.as_ref()).unwrap(), None);
+    }
+
     #[test]
     fn instantiate_multiple_accounts() {
         let mut deps = mock_dependencies(&[]);
@@ -751,6 +1613,7 @@ mod tests {
                     amount: amount2,
                 },
             ],
+            mint: None,
             marketing: None,
         };
         let info = mock_info("creator", &[]);
@@ -868,6 +1731,371 @@ mod tests {
         );
     }
 
+    #[test]
+    fn balance_and_supply_snapshots() {
+        let mut deps = mock_dependencies(&[]);
+        let owner = String::from("OWNER");
+        let addr2 = String::from("addr0002");
+        let amount1 = Uint128::from(12340000u128);
+        let transfer = Uint128::from(76543u128);
+        let burn = Uint128::from(1000u128);
+
+        let mut env = mock_env();
+        let instantiated_at = env.block.height;
+        do_instantiate(deps.as_mut(), &owner, amount1);
+
+        // transfer a few blocks later
+        env.block.height += 5;
+        let transferred_at = env.block.height;
+        let info = mock_info(owner.as_ref(), &[]);
+        let msg = ExecuteMsg::Transfer {
+            recipient: addr2.clone(),
+            amount: transfer,
+        };
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        // burn in the next block
+        env.block.height += 1;
+        let burned_at = env.block.height;
+        let info = mock_info(owner.as_ref(), &[]);
+        let msg = ExecuteMsg::Burn { amount: burn };
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let balance_at = |address: &String, height: u64| {
+            query_balance_at_height(deps.as_ref(), address.clone(), height)
+                .unwrap()
+                .balance
+        };
+        let supply_at = |height: u64| {
+            query_total_supply_at_height(deps.as_ref(), height)
+                .unwrap()
+                .total_supply
+        };
+
+        // nothing existed before instantiation
+        assert_eq!(balance_at(&owner, instantiated_at), Uint128::zero());
+        assert_eq!(supply_at(instantiated_at), Uint128::zero());
+
+        // snapshots reflect the state at the beginning of each block
+        assert_eq!(balance_at(&owner, transferred_at), amount1);
+        assert_eq!(balance_at(&addr2, transferred_at), Uint128::zero());
+        assert_eq!(supply_at(transferred_at), amount1);
+
+        let remainder = amount1.checked_sub(transfer).unwrap();
+        assert_eq!(balance_at(&owner, burned_at), remainder);
+        assert_eq!(balance_at(&addr2, burned_at), transfer);
+        assert_eq!(supply_at(burned_at), amount1);
+
+        let remainder = remainder.checked_sub(burn).unwrap();
+        assert_eq!(balance_at(&owner, burned_at + 1), remainder);
+        assert_eq!(balance_at(&addr2, burned_at + 1), transfer);
+        assert_eq!(supply_at(burned_at + 1), amount1.checked_sub(burn).unwrap());
+    }
+
+    #[test]
+    fn two_step_ownership() {
+        let mut deps = mock_dependencies(&[]);
+        let new_owner = String::from("new_owner");
+        do_instantiate(deps.as_mut(), "addr0001", Uint128::new(1000));
+
+        let msg = ExecuteMsg::ProposeNewOwner { owner: new_owner.clone(), expiry: None };
+        execute(deps.as_mut(), mock_env(), mock_info("OWNER", &[]), msg).unwrap();
+        let pending: PendingOwnerResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::PendingOwner {}).unwrap())
+                .unwrap();
+        assert_eq!(pending.owner, Some(new_owner.clone()));
+
+        // ownership is not moved until accepted
+        assert_eq!(query_config(deps.as_ref()).unwrap().owner, "OWNER");
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("addr0001", &[]),
+            ExecuteMsg::AcceptOwnership {},
+        )
+            .unwrap_err();
+        assert_eq!(err, ContractError::Ownership(OwnershipError::Unauthorized {}));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(new_owner.as_ref(), &[]),
+            ExecuteMsg::AcceptOwnership {},
+        )
+            .unwrap();
+        assert_eq!(query_config(deps.as_ref()).unwrap().owner, new_owner);
+
+        // previous owner lost the rights
+        let err = execute(deps.as_mut(), mock_env(), mock_info("OWNER", &[]), ExecuteMsg::Pause {})
+            .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn pause_blocks_transfers() {
+        let mut deps = mock_dependencies(&[]);
+        let addr1 = String::from("addr0001");
+        let addr2 = String::from("addr0002");
+        let staking = String::from("staking");
+        let amount1 = Uint128::from(12340000u128);
+        let transfer = Uint128::from(76543u128);
+
+        do_instantiate(deps.as_mut(), &addr1, amount1);
+        let msg = ExecuteMsg::Transfer {
+            recipient: staking.clone(),
+            amount: transfer,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(addr1.as_ref(), &[]), msg).unwrap();
+
+        // only owner can pause
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(addr1.as_ref(), &[]),
+            ExecuteMsg::Pause {},
+        )
+            .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let info = mock_info("OWNER", &[]);
+        execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::Pause {}).unwrap();
+        let msg = ExecuteMsg::UpdateConfig {
+            pause_allowlist: Some(vec![staking.clone()]),
+            frozen_can_receive: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert!(query_config(deps.as_ref()).unwrap().paused);
+
+        // nobody outside of the allowlist can move funds
+        let msg = ExecuteMsg::Transfer {
+            recipient: addr2.clone(),
+            amount: transfer,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(addr1.as_ref(), &[]), msg)
+            .unwrap_err();
+        assert_eq!(err, ContractError::Paused {});
+
+        let msg = ExecuteMsg::Send {
+            contract: addr2.clone(),
+            amount: transfer,
+            msg: Binary::default(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(addr1.as_ref(), &[]), msg)
+            .unwrap_err();
+        assert_eq!(err, ContractError::Paused {});
+
+        // allowlisted address still can
+        let msg = ExecuteMsg::Transfer {
+            recipient: addr2.clone(),
+            amount: transfer,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(staking.as_ref(), &[]), msg).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), addr2.clone()), transfer);
+
+        // unpause restores transfers
+        let info = mock_info("OWNER", &[]);
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Unpause {}).unwrap();
+        let msg = ExecuteMsg::Transfer {
+            recipient: addr2.clone(),
+            amount: transfer,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(addr1.as_ref(), &[]), msg).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), addr2), transfer + transfer);
+    }
+
+    #[test]
+    fn frozen_accounts() {
+        let mut deps = mock_dependencies(&[]);
+        let addr1 = String::from("addr0001");
+        let addr2 = String::from("addr0002");
+        let amount1 = Uint128::from(12340000u128);
+        let transfer = Uint128::from(76543u128);
+
+        do_instantiate(deps.as_mut(), &addr1, amount1);
+        let msg = ExecuteMsg::Transfer {
+            recipient: addr2.clone(),
+            amount: transfer,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(addr1.as_ref(), &[]), msg).unwrap();
+
+        // only owner can freeze
+        let msg = ExecuteMsg::FreezeAccount {
+            address: addr2.clone(),
+            reason: "court order".to_string(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(addr1.as_ref(), &[]), msg.clone())
+            .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info("OWNER", &[]), msg).unwrap();
+
+        // frozen account cannot send
+        let msg = ExecuteMsg::Transfer {
+            recipient: addr1.clone(),
+            amount: transfer,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(addr2.as_ref(), &[]), msg)
+            .unwrap_err();
+        assert_eq!(err, ContractError::AccountFrozen { address: addr2.clone() });
+
+        // nor receive by default
+        let msg = ExecuteMsg::Transfer {
+            recipient: addr2.clone(),
+            amount: transfer,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(addr1.as_ref(), &[]), msg.clone())
+            .unwrap_err();
+        assert_eq!(err, ContractError::AccountFrozen { address: addr2.clone() });
+
+        // receiving can be allowed by config
+        let update = ExecuteMsg::UpdateConfig {
+            pause_allowlist: None,
+            frozen_can_receive: Some(true),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("OWNER", &[]), update).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info(addr1.as_ref(), &[]), msg).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), addr2.clone()), transfer + transfer);
+
+        // unfrozen account can send again
+        let msg = ExecuteMsg::UnfreezeAccount {
+            address: addr2.clone(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("OWNER", &[]), msg).unwrap();
+        let msg = ExecuteMsg::Transfer {
+            recipient: addr1.clone(),
+            amount: transfer,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(addr2.as_ref(), &[]), msg).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), addr2), transfer);
+    }
+
+    #[test]
+    fn transfer_fee() {
+        let mut deps = mock_dependencies(&[]);
+        let addr1 = String::from("addr0001");
+        let addr2 = String::from("addr0002");
+        let treasury = String::from("treasury");
+        let amount1 = Uint128::from(1000000u128);
+
+        do_instantiate(deps.as_mut(), &addr1, amount1);
+
+        // only owner can set the fee
+        let fee = TransferFeeInfo {
+            rate_bps: 100,
+            min_fee: Uint128::new(5),
+            max_fee: Some(Uint128::new(50)),
+            treasury: Some(treasury.clone()),
+            exempt: vec![],
+        };
+        let msg = ExecuteMsg::UpdateTransferFee { transfer_fee: Some(fee.clone()) };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(addr1.as_ref(), &[]), msg)
+            .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // min cannot exceed max
+        let msg = ExecuteMsg::UpdateTransferFee {
+            transfer_fee: Some(TransferFeeInfo { min_fee: Uint128::new(51), ..fee.clone() }),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("OWNER", &[]), msg)
+            .unwrap_err();
+        assert_eq!(err, ContractError::InvalidTransferFee {});
+
+        let msg = ExecuteMsg::UpdateTransferFee { transfer_fee: Some(fee.clone()) };
+        execute(deps.as_mut(), mock_env(), mock_info("OWNER", &[]), msg).unwrap();
+
+        // fee is clamped between min and max
+        let simulate = |deps: Deps, amount: u128| {
+            query_simulate_transfer(deps, addr1.clone(), addr2.clone(), Uint128::new(amount))
+                .unwrap()
+        };
+        assert_eq!(simulate(deps.as_ref(), 3).fee, Uint128::new(3));
+        assert_eq!(simulate(deps.as_ref(), 100).fee, Uint128::new(5));
+        assert_eq!(simulate(deps.as_ref(), 2000).fee, Uint128::new(20));
+        assert_eq!(
+            simulate(deps.as_ref(), 100000),
+            SimulateTransferResponse { amount: Uint128::new(99950), fee: Uint128::new(50) }
+        );
+
+        // fee goes to the treasury
+        let msg = ExecuteMsg::Transfer { recipient: addr2.clone(), amount: Uint128::new(2000) };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(addr1.as_ref(), &[]), msg)
+            .unwrap();
+        assert_eq!(res.attributes[4], attr("fee", "20"));
+        assert_eq!(get_balance(deps.as_ref(), &addr1), Uint128::new(998000));
+        assert_eq!(get_balance(deps.as_ref(), &addr2), Uint128::new(1980));
+        assert_eq!(get_balance(deps.as_ref(), &treasury), Uint128::new(20));
+
+        // send notifies the contract with the net amount
+        let msg = ExecuteMsg::Send {
+            contract: addr2.clone(),
+            amount: Uint128::new(1000),
+            msg: Binary::default(),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(addr1.as_ref(), &[]), msg)
+            .unwrap();
+        let binary_msg = Cw20ReceiveMsg {
+            sender: addr1.clone(),
+            amount: Uint128::new(990),
+            msg: Binary::default(),
+        }
+            .into_binary()
+            .unwrap();
+        assert_eq!(
+            res.messages[0],
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: addr2.clone(),
+                msg: binary_msg,
+                funds: vec![],
+            })
+        );
+        assert_eq!(get_balance(deps.as_ref(), &treasury), Uint128::new(30));
+
+        // frozen treasury cannot receive the fee unless frozen accounts can receive
+        let msg = ExecuteMsg::FreezeAccount { address: treasury.clone(), reason: "audit".to_string() };
+        execute(deps.as_mut(), mock_env(), mock_info("OWNER", &[]), msg).unwrap();
+        let msg = ExecuteMsg::Transfer { recipient: addr2.clone(), amount: Uint128::new(1000) };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(addr1.as_ref(), &[]), msg.clone())
+            .unwrap_err();
+        assert_eq!(err, ContractError::AccountFrozen { address: treasury.clone() });
+        assert_eq!(get_balance(deps.as_ref(), &treasury), Uint128::new(30));
+        let update = ExecuteMsg::UpdateConfig {
+            pause_allowlist: None,
+            frozen_can_receive: Some(true),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("OWNER", &[]), update).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info(addr1.as_ref(), &[]), msg).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), &treasury), Uint128::new(40));
+        let msg = ExecuteMsg::UnfreezeAccount { address: treasury.clone() };
+        execute(deps.as_mut(), mock_env(), mock_info("OWNER", &[]), msg).unwrap();
+
+        // exempt addresses are not charged
+        let msg = ExecuteMsg::UpdateTransferFee {
+            transfer_fee: Some(TransferFeeInfo { exempt: vec![addr2.clone()], ..fee.clone() }),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("OWNER", &[]), msg).unwrap();
+        let msg = ExecuteMsg::Transfer { recipient: addr1.clone(), amount: Uint128::new(1000) };
+        execute(deps.as_mut(), mock_env(), mock_info(addr2.as_ref(), &[]), msg).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), &addr2), Uint128::new(2960));
+        assert_eq!(get_balance(deps.as_ref(), &treasury), Uint128::new(40));
+
+        // without treasury the fee is burned
+        let msg = ExecuteMsg::UpdateTransferFee {
+            transfer_fee: Some(TransferFeeInfo { treasury: None, ..fee }),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("OWNER", &[]), msg).unwrap();
+        let msg = ExecuteMsg::Transfer { recipient: addr2.clone(), amount: Uint128::new(1000) };
+        execute(deps.as_mut(), mock_env(), mock_info(addr1.as_ref(), &[]), msg).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), &addr2), Uint128::new(3950));
+        assert_eq!(
+            query_token_info(deps.as_ref()).unwrap().total_supply,
+            amount1 - Uint128::new(10)
+        );
+
+        // disabled fee
+        let msg = ExecuteMsg::UpdateTransferFee { transfer_fee: None };
+        execute(deps.as_mut(), mock_env(), mock_info("OWNER", &[]), msg).unwrap();
+        assert_eq!(simulate(deps.as_ref(), 2000).fee, Uint128::zero());
+    }
+
     #[test]
     fn burn() {
         let mut deps = mock_dependencies(&coins(2, "token"));
@@ -992,6 +2220,134 @@ mod tests {
         );
     }
 
+    #[test]
+    fn batch_transfer() {
+        let mut deps = mock_dependencies(&[]);
+        let addr1 = String::from("addr0001");
+        let addr2 = String::from("addr0002");
+        let addr3 = String::from("addr0003");
+        let amount1 = Uint128::from(12340000u128);
+
+        do_instantiate(deps.as_mut(), &addr1, amount1);
+
+        // cannot transfer nothing
+        let msg = ExecuteMsg::BatchTransfer {
+            transfers: vec![
+                Cw20Coin { address: addr2.clone(), amount: Uint128::new(100) },
+                Cw20Coin { address: addr3.clone(), amount: Uint128::zero() },
+            ],
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(addr1.as_ref(), &[]), msg)
+            .unwrap_err();
+        assert_eq!(err, ContractError::InvalidZeroAmount {});
+
+        // the whole batch fails if the sum exceeds the balance
+        let msg = ExecuteMsg::BatchTransfer {
+            transfers: vec![
+                Cw20Coin { address: addr2.clone(), amount: Uint128::new(12000000) },
+                Cw20Coin { address: addr3.clone(), amount: Uint128::new(400000) },
+            ],
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(addr1.as_ref(), &[]), msg)
+            .unwrap_err();
+        assert!(matches!(err, ContractError::Std(StdError::Overflow { .. })));
+
+        // valid batch
+        let msg = ExecuteMsg::BatchTransfer {
+            transfers: vec![
+                Cw20Coin { address: addr2.clone(), amount: Uint128::new(1000) },
+                Cw20Coin { address: addr3.clone(), amount: Uint128::new(2000) },
+            ],
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(addr1.as_ref(), &[]), msg)
+            .unwrap();
+        assert_eq!(res.messages.len(), 0);
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "batch_transfer"),
+                attr("from", addr1.clone()),
+                attr("total", "3000"),
+                attr("to", addr2.clone()),
+                attr("amount", "1000"),
+                attr("fee", "0"),
+                attr("to", addr3.clone()),
+                attr("amount", "2000"),
+                attr("fee", "0"),
+            ]
+        );
+
+        assert_eq!(
+            get_balance(deps.as_ref(), addr1),
+            amount1.checked_sub(Uint128::new(3000)).unwrap()
+        );
+        assert_eq!(get_balance(deps.as_ref(), addr2), Uint128::new(1000));
+        assert_eq!(get_balance(deps.as_ref(), addr3), Uint128::new(2000));
+        assert_eq!(
+            query_token_info(deps.as_ref()).unwrap().total_supply,
+            amount1
+        );
+    }
+
+    #[test]
+    fn batch_send() {
+        let mut deps = mock_dependencies(&[]);
+        let addr1 = String::from("addr0001");
+        let contract1 = String::from("contract1");
+        let contract2 = String::from("contract2");
+        let amount1 = Uint128::from(12340000u128);
+        let send_msg = Binary::from(r#"{"some":123}"#.as_bytes());
+
+        do_instantiate(deps.as_mut(), &addr1, amount1);
+
+        let msg = ExecuteMsg::BatchSend {
+            sends: vec![
+                BatchSendItem {
+                    contract: contract1.clone(),
+                    amount: Uint128::new(1000),
+                    msg: send_msg.clone(),
+                },
+                BatchSendItem {
+                    contract: contract2.clone(),
+                    amount: Uint128::new(2000),
+                    msg: send_msg.clone(),
+                },
+            ],
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(addr1.as_ref(), &[]), msg)
+            .unwrap();
+        assert_eq!(res.messages.len(), 2);
+
+        // every recipient gets its own receive hook
+        for (i, (contract, amount)) in [(&contract1, 1000u128), (&contract2, 2000u128)]
+            .iter()
+            .enumerate()
+        {
+            let binary_msg = Cw20ReceiveMsg {
+                sender: addr1.clone(),
+                amount: Uint128::new(*amount),
+                msg: send_msg.clone(),
+            }
+                .into_binary()
+                .unwrap();
+            assert_eq!(
+                res.messages[i],
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: contract.to_string(),
+                    msg: binary_msg,
+                    funds: vec![],
+                }))
+            );
+        }
+
+        assert_eq!(
+            get_balance(deps.as_ref(), addr1),
+            amount1.checked_sub(Uint128::new(3000)).unwrap()
+        );
+        assert_eq!(get_balance(deps.as_ref(), contract1), Uint128::new(1000));
+        assert_eq!(get_balance(deps.as_ref(), contract2), Uint128::new(2000));
+    }
+
     mod marketing {
         use super::*;
 
@@ -1004,6 +2360,7 @@ mod tests {
                 symbol: "CASH".to_string(),
                 decimals: 9,
                 initial_balances: vec![],
+                mint: None,
                 marketing: Some(InstantiateMarketingInfo {
                     project: Some("Project".to_owned()),
                     description: Some("Description".to_owned()),
@@ -1058,6 +2415,7 @@ mod tests {
                 symbol: "CASH".to_string(),
                 decimals: 9,
                 initial_balances: vec![],
+                mint: None,
                 marketing: Some(InstantiateMarketingInfo {
                     project: Some("Project".to_owned()),
                     description: Some("Description".to_owned()),
@@ -1111,6 +2469,7 @@ mod tests {
                 symbol: "CASH".to_string(),
                 decimals: 9,
                 initial_balances: vec![],
+                mint: None,
                 marketing: Some(InstantiateMarketingInfo {
                     project: Some("Project".to_owned()),
                     description: Some("Description".to_owned()),
@@ -1164,6 +2523,7 @@ mod tests {
                 symbol: "CASH".to_string(),
                 decimals: 9,
                 initial_balances: vec![],
+                mint: None,
                 marketing: Some(InstantiateMarketingInfo {
                     project: Some("Project".to_owned()),
                     description: Some("Description".to_owned()),
@@ -1217,6 +2577,7 @@ mod tests {
                 symbol: "CASH".to_string(),
                 decimals: 9,
                 initial_balances: vec![],
+                mint: None,
                 marketing: Some(InstantiateMarketingInfo {
                     project: Some("Project".to_owned()),
                     description: Some("Description".to_owned()),
@@ -1270,6 +2631,7 @@ mod tests {
                 symbol: "CASH".to_string(),
                 decimals: 9,
                 initial_balances: vec![],
+                mint: None,
                 marketing: Some(InstantiateMarketingInfo {
                     project: Some("Project".to_owned()),
                     description: Some("Description".to_owned()),
@@ -1323,6 +2685,7 @@ mod tests {
                 symbol: "CASH".to_string(),
                 decimals: 9,
                 initial_balances: vec![],
+                mint: None,
                 marketing: Some(InstantiateMarketingInfo {
                     project: Some("Project".to_owned()),
                     description: Some("Description".to_owned()),
@@ -1380,6 +2743,7 @@ mod tests {
                 symbol: "CASH".to_string(),
                 decimals: 9,
                 initial_balances: vec![],
+                mint: None,
                 marketing: Some(InstantiateMarketingInfo {
                     project: Some("Project".to_owned()),
                     description: Some("Description".to_owned()),
@@ -1433,6 +2797,7 @@ mod tests {
                 symbol: "CASH".to_string(),
                 decimals: 9,
                 initial_balances: vec![],
+                mint: None,
                 marketing: Some(InstantiateMarketingInfo {
                     project: Some("Project".to_owned()),
                     description: Some("Description".to_owned()),
@@ -1482,6 +2847,7 @@ mod tests {
                 symbol: "CASH".to_string(),
                 decimals: 9,
                 initial_balances: vec![],
+                mint: None,
                 marketing: Some(InstantiateMarketingInfo {
                     project: Some("Project".to_owned()),
                     description: Some("Description".to_owned()),
@@ -1532,6 +2898,7 @@ mod tests {
                 symbol: "CASH".to_string(),
                 decimals: 9,
                 initial_balances: vec![],
+                mint: None,
                 marketing: Some(InstantiateMarketingInfo {
                     project: Some("Project".to_owned()),
                     description: Some("Description".to_owned()),
@@ -1583,6 +2950,7 @@ mod tests {
                 symbol: "CASH".to_string(),
                 decimals: 9,
                 initial_balances: vec![],
+                mint: None,
                 marketing: Some(InstantiateMarketingInfo {
                     project: Some("Project".to_owned()),
                     description: Some("Description".to_owned()),
@@ -1633,6 +3001,7 @@ mod tests {
                 symbol: "CASH".to_string(),
                 decimals: 9,
                 initial_balances: vec![],
+                mint: None,
                 marketing: Some(InstantiateMarketingInfo {
                     project: Some("Project".to_owned()),
                     description: Some("Description".to_owned()),
@@ -1690,6 +3059,7 @@ mod tests {
                 symbol: "CASH".to_string(),
                 decimals: 9,
                 initial_balances: vec![],
+                mint: None,
                 marketing: Some(InstantiateMarketingInfo {
                     project: Some("Project".to_owned()),
                     description: Some("Description".to_owned()),
@@ -1740,6 +3110,7 @@ mod tests {
                 symbol: "CASH".to_string(),
                 decimals: 9,
                 initial_balances: vec![],
+                mint: None,
                 marketing: Some(InstantiateMarketingInfo {
                     project: Some("Project".to_owned()),
                     description: Some("Description".to_owned()),
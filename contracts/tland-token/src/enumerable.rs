@@ -1,7 +1,10 @@
-use cosmwasm_std::{Deps, Order, StdResult};
-use cw20::{AllAccountsResponse, AllAllowancesResponse, AllowanceInfo};
+use cosmwasm_std::{Deps, Env, Order, StdResult};
+use cw20::{AllAccountsResponse, AllAllowancesResponse, AllowanceInfo, AllowanceResponse};
 
-use crate::state::{ALLOWANCES, BALANCES};
+use crate::msg::{
+    AllSpenderAllowancesResponse, FrozenAccountInfo, FrozenAccountsResponse, SpenderAllowanceInfo,
+};
+use crate::state::{ALLOWANCES, ALLOWANCES_SPENDER, BALANCES, FROZEN};
 use cw_storage_plus::Bound;
 
 // settings for pagination
@@ -10,17 +13,21 @@ const DEFAULT_LIMIT: u32 = 10;
 
 pub fn query_all_allowances(
     deps: Deps,
+    env: Env,
     owner: String,
     start_after: Option<String>,
     limit: Option<u32>,
+    exclude_expired: Option<bool>,
 ) -> StdResult<AllAllowancesResponse> {
     let owner_addr = deps.api.addr_validate(&owner)?;
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
     let start = start_after.map(Bound::exclusive);
+    let exclude_expired = exclude_expired.unwrap_or(false);
 
     let allowances: StdResult<Vec<AllowanceInfo>> = ALLOWANCES
         .prefix(&owner_addr)
         .range(deps.storage, start, None, Order::Ascending)
+        .filter(|item| !exclude_expired || is_active(item, &env))
         .take(limit)
         .map(|item| {
             let (k, v) = item?;
@@ -36,6 +43,46 @@ pub fn query_all_allowances(
     })
 }
 
+pub fn query_all_spender_allowances(
+    deps: Deps,
+    env: Env,
+    spender: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    exclude_expired: Option<bool>,
+) -> StdResult<AllSpenderAllowancesResponse> {
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let exclude_expired = exclude_expired.unwrap_or(false);
+
+    let allowances: StdResult<Vec<SpenderAllowanceInfo>> = ALLOWANCES_SPENDER
+        .prefix(&spender_addr)
+        .range(deps.storage, start, None, Order::Ascending)
+        .filter(|item| !exclude_expired || is_active(item, &env))
+        .take(limit)
+        .map(|item| {
+            let (k, v) = item?;
+            Ok(SpenderAllowanceInfo {
+                owner: String::from_utf8(k)?,
+                allowance: v.allowance,
+                expires: v.expires,
+            })
+        })
+        .collect();
+    Ok(AllSpenderAllowancesResponse {
+        allowances: allowances?,
+    })
+}
+
+/// Errors are kept so that they surface in the response
+fn is_active(item: &StdResult<(Vec<u8>, AllowanceResponse)>, env: &Env) -> bool {
+    match item {
+        Ok((_, allowance)) => !allowance.expires.is_expired(&env.block),
+        Err(_) => true,
+    }
+}
+
 pub fn query_all_accounts(
     deps: Deps,
     start_after: Option<String>,
@@ -44,10 +91,13 @@ pub fn query_all_accounts(
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
     let start = start_after.map(Bound::exclusive);
 
-    let accounts: Result<Vec<_>, _> = BALANCES
-        .keys(deps.storage, start, None, Order::Ascending)
-        .map(String::from_utf8)
+    let accounts: StdResult<Vec<_>> = BALANCES
+        .range(deps.storage, start, None, Order::Ascending)
         .take(limit)
+        .map(|item| {
+            let (k, _) = item?;
+            Ok(String::from_utf8(k)?)
+        })
         .collect();
 
     Ok(AllAccountsResponse {
@@ -55,6 +105,31 @@ pub fn query_all_accounts(
     })
 }
 
+pub fn query_frozen_accounts(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<FrozenAccountsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let accounts: StdResult<Vec<_>> = FROZEN
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (k, reason) = item?;
+            Ok(FrozenAccountInfo {
+                address: String::from_utf8(k)?,
+                reason,
+            })
+        })
+        .collect();
+
+    Ok(FrozenAccountsResponse {
+        accounts: accounts?,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -77,6 +152,7 @@ mod tests {
                 address: addr.into(),
                 amount,
             }],
+            mint: None,
             marketing: None,
         };
         let info = mock_info("creator", &[]);
@@ -99,7 +175,9 @@ mod tests {
         do_instantiate(deps.as_mut(), &owner, Uint128::new(12340000));
 
         // no allowance to start
-        let allowances = query_all_allowances(deps.as_ref(), owner.clone(), None, None).unwrap();
+        let allowances =
+            query_all_allowances(deps.as_ref(), env.clone(), owner.clone(), None, None, None)
+                .unwrap();
         assert_eq!(allowances.allowances, vec![]);
 
         // set allowance with height expiration
@@ -119,14 +197,18 @@ mod tests {
             amount: allow2,
             expires: None,
         };
-        execute(deps.as_mut(), env, info, msg).unwrap();
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
         // query list gets 2
-        let allowances = query_all_allowances(deps.as_ref(), owner.clone(), None, None).unwrap();
+        let allowances =
+            query_all_allowances(deps.as_ref(), env.clone(), owner.clone(), None, None, None)
+                .unwrap();
         assert_eq!(allowances.allowances.len(), 2);
 
         // first one is spender1 (order of CanonicalAddr uncorrelated with String)
-        let allowances = query_all_allowances(deps.as_ref(), owner.clone(), None, Some(1)).unwrap();
+        let allowances =
+            query_all_allowances(deps.as_ref(), env.clone(), owner.clone(), None, Some(1), None)
+                .unwrap();
         assert_eq!(allowances.allowances.len(), 1);
         let allow = &allowances.allowances[0];
         assert_eq!(&allow.spender, &spender1);
@@ -136,9 +218,11 @@ mod tests {
         // next one is spender2
         let allowances = query_all_allowances(
             deps.as_ref(),
+            env,
             owner,
             Some(allow.spender.clone()),
             Some(10000),
+            None,
         )
             .unwrap();
         assert_eq!(allowances.allowances.len(), 1);
@@ -148,6 +232,109 @@ mod tests {
         assert_eq!(&allow.allowance, &allow2);
     }
 
+    #[test]
+    fn query_all_spender_allowances_works() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        let spender = String::from("spender");
+        // these are in alphabetical order same than insert order
+        let owner1 = String::from("earlier");
+        let owner2 = String::from("later");
+
+        let mut env = mock_env();
+        do_instantiate(deps.as_mut(), &owner1, Uint128::new(12340000));
+
+        // no allowance to start
+        let allowances = query_all_spender_allowances(
+            deps.as_ref(),
+            env.clone(),
+            spender.clone(),
+            None,
+            None,
+            None,
+        )
+            .unwrap();
+        assert_eq!(allowances.allowances, vec![]);
+
+        // owner1 allowance expires soon
+        let expires = Expiration::AtHeight(env.block.height + 10);
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: spender.clone(),
+            amount: Uint128::new(7777),
+            expires: Some(expires),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info(owner1.as_ref(), &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: spender.clone(),
+            amount: Uint128::new(54321),
+            expires: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info(owner2.as_ref(), &[]), msg).unwrap();
+
+        let allowances = query_all_spender_allowances(
+            deps.as_ref(),
+            env.clone(),
+            spender.clone(),
+            None,
+            None,
+            None,
+        )
+            .unwrap();
+        assert_eq!(
+            allowances.allowances,
+            vec![
+                SpenderAllowanceInfo {
+                    owner: owner1.clone(),
+                    allowance: Uint128::new(7777),
+                    expires,
+                },
+                SpenderAllowanceInfo {
+                    owner: owner2.clone(),
+                    allowance: Uint128::new(54321),
+                    expires: Expiration::Never {},
+                },
+            ]
+        );
+
+        // index follows decreases
+        let msg = ExecuteMsg::DecreaseAllowance {
+            spender: spender.clone(),
+            amount: Uint128::new(54320),
+            expires: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info(owner2.as_ref(), &[]), msg).unwrap();
+        let allowances = query_all_spender_allowances(
+            deps.as_ref(),
+            env.clone(),
+            spender.clone(),
+            Some(owner1),
+            None,
+            None,
+        )
+            .unwrap();
+        assert_eq!(allowances.allowances.len(), 1);
+        assert_eq!(allowances.allowances[0].allowance, Uint128::new(1));
+
+        // expired allowances can be skipped
+        env.block.height += 10;
+        let allowances = query_all_spender_allowances(
+            deps.as_ref(),
+            env.clone(),
+            spender.clone(),
+            None,
+            None,
+            None,
+        )
+            .unwrap();
+        assert_eq!(allowances.allowances.len(), 2);
+        let allowances =
+            query_all_spender_allowances(deps.as_ref(), env, spender, None, None, Some(true))
+                .unwrap();
+        assert_eq!(allowances.allowances.len(), 1);
+        assert_eq!(allowances.allowances[0].owner, owner2);
+    }
+
     #[test]
     fn query_all_accounts_works() {
         let mut deps = mock_dependencies(&coins(2, "token"));
@@ -212,4 +399,65 @@ mod tests {
                 .unwrap();
         assert_eq!(accounts.accounts, expected_order[3..].to_vec());
     }
+
+    #[test]
+    fn query_frozen_accounts_works() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        // insert order and lexicographical order are different
+        let acct1 = String::from("zebra");
+        let acct2 = String::from("acct01");
+        let acct3 = String::from("nice");
+
+        do_instantiate(deps.as_mut(), &acct1, Uint128::new(12340000));
+
+        let info = mock_info("OWNER", &[]);
+        let env = mock_env();
+        for acct in [&acct1, &acct2, &acct3] {
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                info.clone(),
+                ExecuteMsg::FreezeAccount {
+                    address: acct.clone(),
+                    reason: format!("{} sanctioned", acct),
+                },
+            )
+                .unwrap();
+        }
+        execute(
+            deps.as_mut(),
+            env,
+            info,
+            ExecuteMsg::UnfreezeAccount {
+                address: acct3,
+            },
+        )
+            .unwrap();
+
+        // make sure we get the proper results
+        let frozen = query_frozen_accounts(deps.as_ref(), None, None).unwrap();
+        assert_eq!(
+            frozen.accounts,
+            vec![
+                FrozenAccountInfo {
+                    address: acct2.clone(),
+                    reason: "acct01 sanctioned".to_string(),
+                },
+                FrozenAccountInfo {
+                    address: acct1.clone(),
+                    reason: "zebra sanctioned".to_string(),
+                },
+            ]
+        );
+
+        // let's do pagination
+        let frozen = query_frozen_accounts(deps.as_ref(), None, Some(1)).unwrap();
+        assert_eq!(frozen.accounts.len(), 1);
+        assert_eq!(frozen.accounts[0].address, acct2);
+
+        let frozen = query_frozen_accounts(deps.as_ref(), Some(acct2), Some(10)).unwrap();
+        assert_eq!(frozen.accounts.len(), 1);
+        assert_eq!(frozen.accounts[0].address, acct1);
+    }
 }
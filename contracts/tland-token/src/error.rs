@@ -1,4 +1,5 @@
 use cosmwasm_std::{OverflowError, StdError};
+use ownership::{MigrationError, OwnershipError};
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -9,21 +10,48 @@ pub enum ContractError {
     #[error("{0}")]
     Overflow(#[from] OverflowError),
 
+    #[error("{0}")]
+    Ownership(#[from] OwnershipError),
+
+    #[error("{0}")]
+    Migration(#[from] MigrationError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
+    #[error("Token transfers are paused")]
+    Paused {},
+
+    #[error("Account {address} is frozen")]
+    AccountFrozen { address: String },
+
+    #[error("Invalid transfer fee config")]
+    InvalidTransferFee {},
+
     #[error("Cannot set to own account")]
     CannotSetOwnAccount {},
 
     #[error("Invalid zero amount")]
     InvalidZeroAmount {},
 
+    #[error("Minting cannot exceed the cap")]
+    CannotExceedCap {},
+
+    #[error("Cap cannot be lower than the total supply")]
+    CapBelowTotalSupply {},
+
     #[error("Allowance is expired")]
     Expired {},
 
     #[error("No allowance for this account")]
     NoAllowance {},
 
+    #[error("Invalid permit signature")]
+    InvalidPermitSignature {},
+
+    #[error("Invalid permit nonce, expected {expected}")]
+    InvalidPermitNonce { expected: u64 },
+
     #[error("Logo binary data exceeds 5KB limit")]
     LogoTooBig {},
 
@@ -32,7 +60,4 @@ pub enum ContractError {
 
     #[error("Invalid png header")]
     InvalidPngHeader {},
-
-    #[error("Cannot migrate from different contract type: {previous_contract}")]
-    CannotMigrate { previous_contract: String },
 }
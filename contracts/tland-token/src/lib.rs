@@ -2,7 +2,9 @@ pub mod allowances;
 pub mod contract;
 pub mod enumerable;
 mod error;
+mod migrations;
 pub mod msg;
 pub mod state;
+pub mod voting;
 
 pub use crate::error::ContractError;
@@ -0,0 +1,158 @@
+use cosmwasm_std::{Addr, DepsMut, Env, Order, StdResult, Storage};
+use cw_storage_plus::Item;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ContractError;
+use crate::state::{
+    ALLOWANCES, ALLOWANCES_SPENDER, CONFIG, Config, TOKEN_INFO, TOTAL_SUPPLY_HISTORY,
+};
+
+/// Config layout stored before 0.2.0
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct ConfigV0_1 {
+    owner: Addr,
+}
+
+const CONFIG_V0_1: Item<ConfigV0_1> = Item::new("config");
+
+/// Upgrades the state stored by the previous contract version to the current layout
+pub fn migrate_from(deps: DepsMut, env: &Env, previous: &Version) -> Result<(), ContractError> {
+    if *previous < Version::new(0, 2, 0) {
+        migrate_to_v0_2_0(deps.storage, env)?;
+    }
+    Ok(())
+}
+
+fn migrate_to_v0_2_0(storage: &mut dyn Storage, env: &Env) -> StdResult<()> {
+    // config gained pause, freeze and transfer fee settings
+    let config = CONFIG_V0_1.load(storage)?;
+    CONFIG.save(
+        storage,
+        &Config {
+            owner: config.owner,
+            paused: false,
+            pause_allowlist: vec![],
+            frozen_can_receive: false,
+            transfer_fee: None,
+        },
+    )?;
+
+    // supply history starts with the supply at migration
+    let token_info = TOKEN_INFO.load(storage)?;
+    TOTAL_SUPPLY_HISTORY.save(storage, &token_info.total_supply, env.block.height)?;
+
+    // allowances were indexed by owner only
+    let allowances = ALLOWANCES
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (key, allowance) in allowances {
+        let (owner, spender) = split_pair_key(&key)?;
+        ALLOWANCES_SPENDER.save(storage, (&spender, &owner), &allowance)?;
+    }
+    Ok(())
+}
+
+/// Splits the raw (owner, spender) key, the owner is prefixed with its length
+fn split_pair_key(key: &[u8]) -> StdResult<(Addr, Addr)> {
+    let len = u16::from_be_bytes([key[0], key[1]]) as usize;
+    let owner = String::from_utf8(key[2..2 + len].to_vec())?;
+    let spender = String::from_utf8(key[2 + len..].to_vec())?;
+    Ok((Addr::unchecked(owner), Addr::unchecked(spender)))
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use cosmwasm_std::Uint128;
+    use cw2::{get_contract_version, set_contract_version};
+    use ownership::MigrationError;
+    use cw20::{AllowanceResponse, Expiration};
+
+    use crate::contract::{migrate, query_total_supply_at_height};
+    use crate::enumerable::query_all_spender_allowances;
+    use crate::msg::MigrateMsg;
+    use crate::state::MinterData;
+
+    use super::*;
+
+    const CONTRACT_NAME: &str = "crates.io:tland-token";
+
+    #[test]
+    fn migrate_from_v0_1() {
+        let mut deps = mock_dependencies(&[]);
+        let env = mock_env();
+        let owner = Addr::unchecked("owner");
+        let spender = Addr::unchecked("spender");
+
+        // state as stored by 0.1.0
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "0.1.0").unwrap();
+        deps.storage.set(b"config", br#"{"owner":"owner"}"#);
+        deps.storage.set(
+            b"token_info",
+            br#"{"name":"Terraland token","symbol":"TLAND","decimals":6,"total_supply":"1000"}"#,
+        );
+        let allowance = AllowanceResponse {
+            allowance: Uint128::new(100),
+            expires: Expiration::Never {},
+        };
+        ALLOWANCES.save(&mut deps.storage, (&owner, &spender), &allowance).unwrap();
+
+        let res = migrate(deps.as_mut(), env.clone(), MigrateMsg {}).unwrap();
+        assert_eq!(res.attributes[1].value, "0.1.0");
+
+        assert_eq!(
+            CONFIG.load(&deps.storage).unwrap(),
+            Config {
+                owner,
+                paused: false,
+                pause_allowlist: vec![],
+                frozen_can_receive: false,
+                transfer_fee: None,
+            }
+        );
+        let token_info = TOKEN_INFO.load(&deps.storage).unwrap();
+        assert_eq!(token_info.mint, None::<MinterData>);
+        let supply = query_total_supply_at_height(deps.as_ref(), env.block.height + 1).unwrap();
+        assert_eq!(supply.total_supply, Uint128::new(1000));
+
+        let allowances = query_all_spender_allowances(
+            deps.as_ref(),
+            env,
+            spender.to_string(),
+            None,
+            None,
+            None,
+        )
+            .unwrap();
+        assert_eq!(allowances.allowances.len(), 1);
+        assert_eq!(allowances.allowances[0].owner, "owner");
+        assert_eq!(allowances.allowances[0].allowance, Uint128::new(100));
+
+        let version = get_contract_version(&deps.storage).unwrap();
+        assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+    }
+
+    #[test]
+    fn refuse_downgrade() {
+        let mut deps = mock_dependencies(&[]);
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "99.0.0").unwrap();
+
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Migration(MigrationError::CannotMigrateVersion {
+                previous_version: "99.0.0".to_string()
+            })
+        );
+
+        set_contract_version(&mut deps.storage, "crates.io:other", "0.1.0").unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Migration(MigrationError::CannotMigrate {
+                previous_contract: "crates.io:other".to_string()
+            })
+        );
+    }
+}
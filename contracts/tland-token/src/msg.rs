@@ -1,5 +1,5 @@
 use cosmwasm_std::{StdError, StdResult, Uint128, Binary};
-use cw20::{Cw20Coin, Logo};
+use cw20::{Cw20Coin, Logo, MinterResponse};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use cw0::Expiration;
@@ -19,10 +19,15 @@ pub struct InstantiateMsg {
     pub symbol: String,
     pub decimals: u8,
     pub initial_balances: Vec<Cw20Coin>,
+    pub mint: Option<MinterResponse>,
     pub marketing: Option<InstantiateMarketingInfo>,
 }
 
 impl InstantiateMsg {
+    pub fn get_cap(&self) -> Option<Uint128> {
+        self.mint.as_ref().and_then(|v| v.cap)
+    }
+
     pub fn validate(&self) -> StdResult<()> {
         // Check name, symbol, decimals
         if !is_valid_name(&self.name) {
@@ -67,16 +72,47 @@ fn is_valid_symbol(symbol: &str) -> bool {
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Config {},
+    /// Returns the proposed owner which has not accepted the ownership yet.
+    /// Return type: PendingOwnerResponse.
+    PendingOwner {},
     /// Returns the current balance of the given address, 0 if unset.
     /// Return type: BalanceResponse.
     Balance { address: String },
+    /// Returns the balance of the given address at the beginning of the block
+    /// with the given height, 0 if unset.
+    /// Return type: BalanceResponse.
+    BalanceAtHeight { address: String, height: u64 },
+    /// Returns the voting power delegated to the address at the beginning of the block
+    /// at the given height.
+    /// Return type: VotingPowerResponse.
+    VotingPower { address: String, height: u64 },
+    /// Returns the account the address delegated its voting power to.
+    /// Return type: DelegatesResponse.
+    Delegates { address: String },
+    /// Returns the amount received and the fee charged for the given transfer.
+    /// Return type: SimulateTransferResponse.
+    SimulateTransfer {
+        from: String,
+        to: String,
+        amount: Uint128,
+    },
     /// Returns metadata on the contract - name, decimals, supply, etc.
     /// Return type: TokenInfoResponse.
     TokenInfo {},
+    /// Returns the total supply at the beginning of the block with the given height.
+    /// Return type: TotalSupplyResponse.
+    TotalSupplyAtHeight { height: u64 },
+    /// Only with "mintable" extension.
+    /// Returns who can mint and the hard cap on maximum tokens after minting.
+    /// Return type: MinterResponse.
+    Minter {},
     /// Only with "allowance" extension.
     /// Returns how much spender can use from owner account, 0 if unset.
     /// Return type: AllowanceResponse.
     Allowance { owner: String, spender: String },
+    /// Returns the nonce expected in the next signed permit of the owner.
+    /// Return type: PermitNonceResponse.
+    PermitNonce { owner: String },
     /// Only with "enumerable" extension (and "allowances")
     /// Returns all allowances this owner has approved. Supports pagination.
     /// Return type: AllAllowancesResponse.
@@ -84,6 +120,17 @@ pub enum QueryMsg {
         owner: String,
         start_after: Option<String>,
         limit: Option<u32>,
+        /// Skips allowances that already expired
+        exclude_expired: Option<bool>,
+    },
+    /// Returns all allowances granted to this spender. Supports pagination.
+    /// Return type: AllSpenderAllowancesResponse.
+    AllSpenderAllowances {
+        spender: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        /// Skips allowances that already expired
+        exclude_expired: Option<bool>,
     },
     /// Only with "enumerable" extension
     /// Returns all accounts that have balances. Supports pagination.
@@ -92,9 +139,16 @@ pub enum QueryMsg {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    /// Returns all frozen accounts with the reason of freezing. Supports pagination.
+    /// Return type: FrozenAccountsResponse.
+    ListFrozenAccounts {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
     /// Only with "marketing" extension
     /// Returns more metadata on the contract to display in the client:
     /// - description, logo, project url, etc.
+    ///
     /// Return type: MarketingInfoResponse
     MarketingInfo {},
     /// Only with "marketing" extension
@@ -104,6 +158,87 @@ pub enum QueryMsg {
     DownloadLogo {},
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TotalSupplyResponse {
+    pub total_supply: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VotingPowerResponse {
+    pub power: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DelegatesResponse {
+    pub delegatee: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TransferFeeInfo {
+    /// Fee in basis points of the transferred amount
+    pub rate_bps: u64,
+    pub min_fee: Uint128,
+    pub max_fee: Option<Uint128>,
+    /// Receives the fees, they are burned if not set
+    pub treasury: Option<String>,
+    /// Transfers from or to these addresses are not charged
+    pub exempt: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateTransferResponse {
+    /// Amount credited to the recipient
+    pub amount: Uint128,
+    pub fee: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BatchSendItem {
+    pub contract: String,
+    pub amount: Uint128,
+    pub msg: Binary,
+}
+
+/// Message signed by the owner to grant an allowance with `Permit`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PermitPayload {
+    pub chain_id: String,
+    pub contract: String,
+    pub owner: String,
+    pub spender: String,
+    pub amount: Uint128,
+    pub expires: Option<Expiration>,
+    pub nonce: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PermitNonceResponse {
+    pub nonce: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SpenderAllowanceInfo {
+    pub owner: String,
+    pub allowance: Uint128,
+    pub expires: Expiration,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AllSpenderAllowancesResponse {
+    pub allowances: Vec<SpenderAllowanceInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FrozenAccountInfo {
+    pub address: String,
+    pub reason: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FrozenAccountsResponse {
+    pub accounts: Vec<FrozenAccountInfo>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct MigrateMsg {}
 
@@ -111,8 +246,32 @@ pub struct MigrateMsg {}
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
     UpdateConfig {
-        owner: Option<String>,
+        /// Addresses which can still move funds while the contract is paused
+        pause_allowlist: Option<Vec<String>>,
+        /// Whether frozen accounts can still receive tokens
+        frozen_can_receive: Option<bool>,
+    },
+    /// Proposes the new owner, who has to accept the ownership before expiry (owner only)
+    ProposeNewOwner {
+        owner: String,
+        expiry: Option<ownership::Expiration>,
     },
+    /// Makes the sender the owner if it was proposed
+    AcceptOwnership {},
+    /// Withdraws the pending ownership proposal (owner only)
+    CancelOwnershipProposal {},
+    /// Blocks transfers, sends, burns and allowance spending (owner only)
+    Pause {},
+    /// Lifts the pause (owner only)
+    Unpause {},
+    /// Delegates the voting power of the sender's balance, the sender can delegate to itself
+    Delegate { delegatee: String },
+    /// Sets the fee charged on transfers, None disables it (owner only)
+    UpdateTransferFee { transfer_fee: Option<TransferFeeInfo> },
+    /// Blocks the account from sending tokens and spending allowances (owner only)
+    FreezeAccount { address: String, reason: String },
+    /// Removes the account from the frozen list (owner only)
+    UnfreezeAccount { address: String },
     /// Transfer is a base message to move tokens to another account without triggering actions
     Transfer { recipient: String, amount: Uint128 },
     /// Burn is a base message to destroy tokens forever
@@ -124,6 +283,20 @@ pub enum ExecuteMsg {
         amount: Uint128,
         msg: Binary,
     },
+    /// Moves tokens to many accounts at once. The sender is debited once with the sum
+    /// of all amounts, the whole batch fails if the balance is insufficient.
+    BatchTransfer { transfers: Vec<Cw20Coin> },
+    /// Like BatchTransfer, but triggers the Receive hook on every recipient contract
+    BatchSend { sends: Vec<BatchSendItem> },
+    /// Only with the "mintable" extension. If authorized, creates amount new tokens
+    /// and adds to the recipient balance.
+    Mint { recipient: String, amount: Uint128 },
+    /// Only with the "mintable" extension. The owner can change the minter and the cap.
+    /// Setting minter to None disables minting, setting cap to None removes the cap.
+    UpdateMinter {
+        minter: Option<String>,
+        cap: Option<Uint128>,
+    },
     /// Only with "approval" extension. Allows spender to access an additional amount tokens
     /// from the owner's (env.sender) account. If expires is Some(), overwrites current allowance
     /// expiration with this one.
@@ -140,6 +313,18 @@ pub enum ExecuteMsg {
         amount: Uint128,
         expires: Option<Expiration>,
     },
+    /// Sets the spender's allowance on the owner's account from an off-chain signed permit,
+    /// so anyone can submit it on the owner's behalf. The signature is checked against
+    /// sha256 of the JSON encoded PermitPayload and the pubkey must belong to the owner.
+    Permit {
+        owner: String,
+        spender: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+        nonce: u64,
+        signature: Binary,
+        pubkey: Binary,
+    },
     /// Only with "approval" extension. Transfers amount tokens from owner -> recipient
     /// if `env.sender` has sufficient pre-approval.
     TransferFrom {
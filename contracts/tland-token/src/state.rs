@@ -2,7 +2,7 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cosmwasm_std::{Addr, Uint128};
-use cw_storage_plus::{Item, Map};
+use cw_storage_plus::{Item, Map, SnapshotItem, SnapshotMap, Strategy};
 
 use cw20::{AllowanceResponse, Logo, MarketingInfoResponse};
 
@@ -13,16 +13,100 @@ pub struct TokenInfo {
     pub symbol: String,
     pub decimals: u8,
     pub total_supply: Uint128,
+    pub mint: Option<MinterData>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct MinterData {
+    pub minter: Addr,
+    /// cap is a hard cap on total supply that can be achieved by minting.
+    /// Note that this refers to total_supply. If None, there is unlimited cap.
+    pub cap: Option<Uint128>,
+}
+
+impl TokenInfo {
+    pub fn get_cap(&self) -> Option<Uint128> {
+        self.mint.as_ref().and_then(|v| v.cap)
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
     pub owner: Addr,
+    /// When set, token movements are blocked for everyone except `pause_allowlist`
+    #[serde(default)]
+    pub paused: bool,
+    #[serde(default)]
+    pub pause_allowlist: Vec<Addr>,
+    /// Whether frozen accounts can still receive tokens
+    #[serde(default)]
+    pub frozen_can_receive: bool,
+    #[serde(default)]
+    pub transfer_fee: Option<TransferFee>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TransferFee {
+    /// Fee in basis points of the transferred amount
+    pub rate_bps: u64,
+    pub min_fee: Uint128,
+    pub max_fee: Option<Uint128>,
+    /// Receives the fees, they are burned if not set
+    pub treasury: Option<Addr>,
+    /// Transfers from or to these addresses are not charged
+    pub exempt: Vec<Addr>,
+}
+
+impl TransferFee {
+    /// Fee for moving amount between the accounts, never more than the amount itself
+    pub fn compute(&self, from: &Addr, to: &Addr, amount: Uint128) -> Uint128 {
+        if self.exempt.contains(from) || self.exempt.contains(to) {
+            return Uint128::zero();
+        }
+        let mut fee = amount.multiply_ratio(self.rate_bps, 10_000u64).max(self.min_fee);
+        if let Some(max_fee) = self.max_fee {
+            fee = fee.min(max_fee);
+        }
+        fee.min(amount)
+    }
+}
+
+impl Config {
+    pub fn can_move_funds(&self, sender: &Addr) -> bool {
+        !self.paused || self.pause_allowlist.contains(sender)
+    }
 }
 
 pub const TOKEN_INFO: Item<TokenInfo> = Item::new("token_info");
 pub const MARKETING_INFO: Item<MarketingInfoResponse> = Item::new("marketing_info");
 pub const LOGO: Item<Logo> = Item::new("logo");
-pub const BALANCES: Map<&Addr, Uint128> = Map::new("balance");
+pub const BALANCES: SnapshotMap<&Addr, Uint128> = SnapshotMap::new(
+    "balance",
+    "balance__checkpoints",
+    "balance__changelog",
+    Strategy::EveryBlock,
+);
+pub const TOTAL_SUPPLY_HISTORY: SnapshotItem<Uint128> = SnapshotItem::new(
+    "total_supply",
+    "total_supply__checkpoints",
+    "total_supply__changelog",
+    Strategy::EveryBlock,
+);
 pub const ALLOWANCES: Map<(&Addr, &Addr), AllowanceResponse> = Map::new("allowance");
+/// Same allowances keyed by (spender, owner), kept in sync with `ALLOWANCES`
+pub const ALLOWANCES_SPENDER: Map<(&Addr, &Addr), AllowanceResponse> =
+    Map::new("allowance_spender");
+/// Next nonce expected in a signed permit from the owner
+pub const PERMIT_NONCES: Map<&Addr, u64> = Map::new("permit_nonce");
 pub const CONFIG: Item<Config> = Item::new("config");
+/// Frozen accounts with the reason of freezing
+pub const FROZEN: Map<&Addr, String> = Map::new("frozen");
+/// Account the holder delegated its voting power to
+pub const DELEGATES: Map<&Addr, Addr> = Map::new("delegates");
+/// Voting power delegated to the account, checkpointed on every change
+pub const VOTING_POWER: SnapshotMap<&Addr, Uint128> = SnapshotMap::new(
+    "voting_power",
+    "voting_power__checkpoints",
+    "voting_power__changelog",
+    Strategy::EveryBlock,
+);
@@ -0,0 +1,187 @@
+use cosmwasm_std::{Addr, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Storage, Uint128};
+
+use crate::error::ContractError;
+use crate::msg::{DelegatesResponse, VotingPowerResponse};
+use crate::state::{BALANCES, DELEGATES, VOTING_POWER};
+
+/// Balances carry voting power only once their holder delegates it, self-delegation included.
+pub fn execute_delegate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    delegatee: String,
+) -> Result<Response, ContractError> {
+    let delegatee_addr = deps.api.addr_validate(&delegatee)?;
+    let previous = DELEGATES.may_load(deps.storage, &info.sender)?;
+    DELEGATES.save(deps.storage, &info.sender, &delegatee_addr)?;
+
+    let balance = BALANCES
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    shift_voting_power(
+        deps.storage,
+        env.block.height,
+        previous.as_ref(),
+        Some(&delegatee_addr),
+        balance,
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "delegate")
+        .add_attribute("delegator", info.sender)
+        .add_attribute("from_delegatee", previous.map(String::from).unwrap_or_default())
+        .add_attribute("to_delegatee", delegatee_addr))
+}
+
+/// Follows a balance change of amount from one account to another, None stands for
+/// minted or burned tokens. Moves the voting power between their delegatees.
+pub fn move_voting_power(
+    storage: &mut dyn Storage,
+    height: u64,
+    from: Option<&Addr>,
+    to: Option<&Addr>,
+    amount: Uint128,
+) -> StdResult<()> {
+    let from = match from {
+        Some(addr) => DELEGATES.may_load(storage, addr)?,
+        None => None,
+    };
+    let to = match to {
+        Some(addr) => DELEGATES.may_load(storage, addr)?,
+        None => None,
+    };
+    shift_voting_power(storage, height, from.as_ref(), to.as_ref(), amount)
+}
+
+fn shift_voting_power(
+    storage: &mut dyn Storage,
+    height: u64,
+    from: Option<&Addr>,
+    to: Option<&Addr>,
+    amount: Uint128,
+) -> StdResult<()> {
+    if from == to || amount.is_zero() {
+        return Ok(());
+    }
+    if let Some(from) = from {
+        VOTING_POWER.update(storage, from, height, |power| -> StdResult<_> {
+            Ok(power.unwrap_or_default().checked_sub(amount)?)
+        })?;
+    }
+    if let Some(to) = to {
+        VOTING_POWER.update(storage, to, height, |power| -> StdResult<_> {
+            Ok(power.unwrap_or_default() + amount)
+        })?;
+    }
+    Ok(())
+}
+
+pub fn query_voting_power(
+    deps: Deps,
+    address: String,
+    height: u64,
+) -> StdResult<VotingPowerResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let power = VOTING_POWER
+        .may_load_at_height(deps.storage, &address, height)?
+        .unwrap_or_default();
+    Ok(VotingPowerResponse { power })
+}
+
+pub fn query_delegates(deps: Deps, address: String) -> StdResult<DelegatesResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let delegatee = DELEGATES.may_load(deps.storage, &address)?;
+    Ok(DelegatesResponse {
+        delegatee: delegatee.map(String::from),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{DepsMut, Uint128};
+    use cw20::{Cw20Coin, TokenInfoResponse};
+
+    use crate::contract::{execute, instantiate, query_token_info};
+    use crate::msg::{ExecuteMsg, InstantiateMsg};
+
+    // this will set up the instantiation for other tests
+    fn do_instantiate(mut deps: DepsMut, addr: &str, amount: Uint128) -> TokenInfoResponse {
+        let instantiate_msg = InstantiateMsg {
+            owner: "OWNER".to_string(),
+            name: "Auto Gen".to_string(),
+            symbol: "AUTO".to_string(),
+            decimals: 3,
+            initial_balances: vec![Cw20Coin {
+                address: addr.into(),
+                amount,
+            }],
+            mint: None,
+            marketing: None,
+        };
+        let info = mock_info("creator", &[]);
+        let env = mock_env();
+        instantiate(deps.branch(), env, info, instantiate_msg).unwrap();
+        query_token_info(deps.as_ref()).unwrap()
+    }
+
+    fn power(deps: Deps, address: &str, height: u64) -> Uint128 {
+        query_voting_power(deps, address.to_string(), height)
+            .unwrap()
+            .power
+    }
+
+    #[test]
+    fn delegate_and_track_voting_power() {
+        let mut deps = mock_dependencies(&[]);
+        let addr1 = String::from("addr0001");
+        let addr2 = String::from("addr0002");
+        let voter = String::from("voter");
+
+        let mut env = mock_env();
+        let start = env.block.height;
+        do_instantiate(deps.as_mut(), &addr1, Uint128::new(1000));
+
+        // undelegated balances carry no votes
+        assert_eq!(query_delegates(deps.as_ref(), addr1.clone()).unwrap().delegatee, None);
+        assert_eq!(power(deps.as_ref(), &addr1, start + 1), Uint128::zero());
+
+        let msg = ExecuteMsg::Delegate { delegatee: voter.clone() };
+        execute(deps.as_mut(), env.clone(), mock_info(addr1.as_ref(), &[]), msg).unwrap();
+        assert_eq!(
+            query_delegates(deps.as_ref(), addr1.clone()).unwrap().delegatee,
+            Some(voter.clone())
+        );
+        assert_eq!(power(deps.as_ref(), &voter, start + 1), Uint128::new(1000));
+
+        // transfer to an undelegated account drops the votes
+        env.block.height += 1;
+        let msg = ExecuteMsg::Transfer { recipient: addr2.clone(), amount: Uint128::new(300) };
+        execute(deps.as_mut(), env.clone(), mock_info(addr1.as_ref(), &[]), msg).unwrap();
+        assert_eq!(power(deps.as_ref(), &voter, start + 2), Uint128::new(700));
+
+        // self delegation carries the received tokens
+        env.block.height += 1;
+        let msg = ExecuteMsg::Delegate { delegatee: addr2.clone() };
+        execute(deps.as_mut(), env.clone(), mock_info(addr2.as_ref(), &[]), msg).unwrap();
+        let msg = ExecuteMsg::Transfer { recipient: addr2.clone(), amount: Uint128::new(200) };
+        execute(deps.as_mut(), env.clone(), mock_info(addr1.as_ref(), &[]), msg).unwrap();
+        assert_eq!(power(deps.as_ref(), &voter, start + 3), Uint128::new(500));
+        assert_eq!(power(deps.as_ref(), &addr2, start + 3), Uint128::new(500));
+
+        // redelegation moves the whole balance
+        env.block.height += 1;
+        let msg = ExecuteMsg::Delegate { delegatee: addr2.clone() };
+        execute(deps.as_mut(), env, mock_info(addr1.as_ref(), &[]), msg).unwrap();
+        assert_eq!(power(deps.as_ref(), &voter, start + 4), Uint128::zero());
+        assert_eq!(power(deps.as_ref(), &addr2, start + 4), Uint128::new(1000));
+
+        // history is preserved
+        assert_eq!(power(deps.as_ref(), &voter, start), Uint128::zero());
+        assert_eq!(power(deps.as_ref(), &voter, start + 1), Uint128::new(1000));
+        assert_eq!(power(deps.as_ref(), &voter, start + 2), Uint128::new(700));
+        assert_eq!(power(deps.as_ref(), &addr2, start + 2), Uint128::zero());
+    }
+}
@@ -3,15 +3,19 @@ use cosmwasm_std::{BankMsg, Binary, Coin, Deps, DepsMut, Env, MessageInfo, Order
 use cosmwasm_std::entry_point;
 use cw0::{maybe_addr, must_pay};
 use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg};
-use cw2::{get_contract_version, set_contract_version};
+use cw2::set_contract_version;
 use cw_storage_plus::Bound;
+use ownership::{accept_ownership, cancel_ownership_proposal, check_migration, MigrationError, propose_new_owner, query_pending_owner};
+use semver::Version;
 
 use crate::error::ContractError;
 use crate::msg::{ExecuteMsg, InstantiateMsg, MemberListResponse, MemberListResponseItem, MemberResponse, MemberResponseItem, MigrateMsg, QueryMsg, RegisterMemberItem};
 use crate::state::{CONFIG, Config, FeeConfig, Member, MEMBERS, State, STATE, Vesting};
 
 // version info for migration info
-const CONTRACT_NAME: &str = "crates.io:airdrop";
+const CONTRACT_NAME: &str = "crates.io:vesting";
+/// Name stored by versions before 0.2.0
+const LEGACY_CONTRACT_NAME: &str = "crates.io:airdrop";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -39,13 +43,21 @@ pub fn instantiate(
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
-    let version = get_contract_version(deps.storage)?;
-    if version.contract != CONTRACT_NAME {
-        return Err(ContractError::CannotMigrate {
-            previous_contract: version.contract,
-        });
+    let previous = check_migration(deps.storage, &[CONTRACT_NAME, LEGACY_CONTRACT_NAME], CONTRACT_VERSION)?;
+    if previous.contract == LEGACY_CONTRACT_NAME {
+        // legacy name is accepted only from versions which used it
+        if previous.version >= Version::new(0, 2, 0) {
+            return Err(MigrationError::CannotMigrate { previous_contract: previous.contract }.into());
+        }
+        // make sure it is not an airdrop stored under the same name
+        CONFIG.load(deps.storage)?;
     }
-    Ok(Response::default())
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("previous_version", previous.version.to_string())
+        .add_attribute("new_version", CONTRACT_VERSION))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -56,8 +68,17 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::UpdateConfig { owner, name, fee_config, vesting } =>
-            execute_update_config(deps, env, info, owner, name, fee_config, vesting),
+        ExecuteMsg::UpdateConfig { name, fee_config, vesting } =>
+            execute_update_config(deps, env, info, name, fee_config, vesting),
+        ExecuteMsg::ProposeNewOwner { owner, expiry } => {
+            let cfg = CONFIG.load(deps.storage)?;
+            Ok(propose_new_owner(deps, env, info, &cfg.owner, owner, expiry)?)
+        }
+        ExecuteMsg::AcceptOwnership {} => execute_accept_ownership(deps, env, info),
+        ExecuteMsg::CancelOwnershipProposal {} => {
+            let cfg = CONFIG.load(deps.storage)?;
+            Ok(cancel_ownership_proposal(deps, info, &cfg.owner)?)
+        }
         ExecuteMsg::RegisterMembers(members) =>
             execute_register_members(deps, env, info, members),
         ExecuteMsg::Claim {} => execute_claim(deps, env, info),
@@ -72,7 +93,6 @@ pub fn execute_update_config(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    new_owner: Option<String>,
     new_name: Option<String>,
     new_fee_config: Option<Vec<FeeConfig>>,
     new_vesting: Option<Vesting>,
@@ -83,13 +103,7 @@ pub fn execute_update_config(
         return Err(ContractError::Unauthorized {});
     }
 
-    let api = deps.api;
-
     CONFIG.update(deps.storage, |mut existing_config| -> StdResult<_> {
-        // update new owner if set
-        if let Some(addr) = new_owner {
-            existing_config.owner = api.addr_validate(&addr)?;
-        }
         if let Some(name) = new_name {
             existing_config.name = name;
         }
@@ -107,6 +121,22 @@ pub fn execute_update_config(
         .add_attribute("sender", info.sender))
 }
 
+pub fn execute_accept_ownership(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let new_owner = accept_ownership(deps.storage, &env, &info.sender)?;
+    CONFIG.update(deps.storage, |mut existing_config| -> StdResult<_> {
+        existing_config.owner = new_owner;
+        Ok(existing_config)
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "accept_ownership")
+        .add_attribute("owner", info.sender))
+}
+
 pub fn execute_register_members(
     deps: DepsMut,
     _env: Env,
@@ -190,9 +220,9 @@ pub fn execute_claim(
 
 fn compute_available_amount(member: &Member, cfg: &Config, time: u64) -> Uint128 {
     // calculate released amount for the member
-    let released_amount = compute_released_amount(&member, &cfg, time);
+    let released_amount = compute_released_amount(member, cfg, time);
     // available amount to claim is decreased by already claimed tokens
-    return released_amount - member.claimed;
+    released_amount - member.claimed
 }
 
 fn compute_released_amount(member: &Member, cfg: &Config, time: u64) -> Uint128 {
@@ -219,7 +249,7 @@ fn compute_released_amount(member: &Member, cfg: &Config, time: u64) -> Uint128
     let days_passed = (time - cfg.vesting.cliff_end_time) / DAY;
 
     // after cliff ends smart contract release initial_amount + rest daily
-    return (member.amount - initial_amount) * Uint128::from(days_passed) / Uint128::from(total_days) + initial_amount;
+    (member.amount - initial_amount) * Uint128::from(days_passed) / Uint128::from(total_days) + initial_amount
 }
 
 pub fn execute_ust_withdraw(
@@ -314,6 +344,7 @@ fn must_pay_fee(info: &MessageInfo, cfg: &Config, operation: String) -> Result<(
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::PendingOwner {} => to_binary(&query_pending_owner(deps)?),
         QueryMsg::State {} => to_binary(&query_state(deps)?),
         QueryMsg::Member { address } =>
             to_binary(&query_member(deps, address, env.block.time.seconds())?),
@@ -323,11 +354,11 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
 }
 
 pub fn query_config(deps: Deps) -> StdResult<Config> {
-    Ok(CONFIG.load(deps.storage)?)
+    CONFIG.load(deps.storage)
 }
 
 pub fn query_state(deps: Deps) -> StdResult<State> {
-    Ok(STATE.load(deps.storage)?)
+    STATE.load(deps.storage)
 }
 
 pub fn query_member(deps: Deps, addr: String, time: u64) -> StdResult<MemberResponse> {
@@ -335,14 +366,11 @@ pub fn query_member(deps: Deps, addr: String, time: u64) -> StdResult<MemberResp
     let cfg = CONFIG.load(deps.storage)?;
     let member = MEMBERS.may_load(deps.storage, &addr)?;
 
-    let res: Option<MemberResponseItem> = match member {
-        Some(m) => Some(MemberResponseItem {
-            amount: m.amount,
-            available_to_claim: compute_available_amount(&m, &cfg, time),
-            claimed: m.claimed,
-        }),
-        None => None,
-    };
+    let res: Option<MemberResponseItem> = member.map(|m| MemberResponseItem {
+        amount: m.amount,
+        available_to_claim: compute_available_amount(&m, &cfg, time),
+        claimed: m.claimed,
+    });
 
     Ok(MemberResponse { member: res })
 }
@@ -389,8 +417,14 @@ mod tests {
     use cosmwasm_std::{Coin, Deps, DepsMut, Env, Uint128};
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
 
-    use crate::contract::{execute, instantiate, query_config, query_member};
-    use crate::msg::{ExecuteMsg, InstantiateMsg, MemberResponseItem, RegisterMemberItem};
+    use ownership::{MigrationError, OwnershipError};
+
+    use cosmwasm_std::Storage;
+    use cw2::{get_contract_version, set_contract_version};
+
+    use crate::contract::{execute, instantiate, migrate, query_config, query_member};
+    use crate::error::ContractError;
+    use crate::msg::{ExecuteMsg, InstantiateMsg, MemberResponseItem, MigrateMsg, RegisterMemberItem};
     use crate::state::{FeeConfig, Vesting};
 
     const INIT_ADMIN: &str = "admin";
@@ -445,7 +479,7 @@ mod tests {
         let mut env = mock_env();
         env.block.height += height_delta;
         env.block.time = env.block.time.plus_seconds(height_delta * 6);
-        return env;
+        env
     }
 
     fn assert_users(
@@ -543,4 +577,67 @@ mod tests {
         assert_available_to_claim(deps.as_ref(), 0, 500_000, 100800);
         assert_claimed(deps.as_ref(), 100_000, 0, 100800);
     }
+
+    #[test]
+    fn two_step_ownership() {
+        let mut deps = mock_dependencies(&[]);
+        let env = mock_env();
+        default_instantiate(deps.as_mut(), env.clone());
+
+        let msg = ExecuteMsg::ProposeNewOwner { owner: USER1.into(), expiry: None };
+        execute(deps.as_mut(), env.clone(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+
+        // proposal can be withdrawn before it is accepted
+        let msg = ExecuteMsg::CancelOwnershipProposal {};
+        execute(deps.as_mut(), env.clone(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+        let msg = ExecuteMsg::AcceptOwnership {};
+        let err = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::Ownership(OwnershipError::NoPendingOwner {}));
+
+        let msg = ExecuteMsg::ProposeNewOwner { owner: USER2.into(), expiry: None };
+        execute(deps.as_mut(), env.clone(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+        let msg = ExecuteMsg::AcceptOwnership {};
+        execute(deps.as_mut(), env, mock_info(USER2, &[]), msg).unwrap();
+        assert_eq!(USER2, query_config(deps.as_ref()).unwrap().owner.as_str());
+    }
+
+    #[test]
+    fn migrate_from_v0_1() {
+        let mut deps = mock_dependencies(&[]);
+
+        // state as stored by 0.1.0
+        set_contract_version(&mut deps.storage, "crates.io:airdrop", "0.1.0").unwrap();
+        deps.storage.set(
+            b"config",
+            br#"{"owner":"admin","terraland_token":"tland1234567890","name":"VESTING","fee_config":[],
+                "vesting":{"start_time":1,"end_time":2,"initial_percentage":10,"cliff_end_time":1}}"#,
+        );
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        let version = get_contract_version(&deps.storage).unwrap();
+        assert_eq!(version.contract, "crates.io:vesting");
+        assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(NAME, query_config(deps.as_ref()).unwrap().name.as_str());
+
+        // airdrop state is refused
+        set_contract_version(&mut deps.storage, "crates.io:airdrop", "0.1.0").unwrap();
+        deps.storage.set(
+            b"config",
+            br#"{"owner":"admin","terraland_token":"tland1234567890","fee_config":[],
+                "mission_smart_contracts":{}}"#,
+        );
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+    }
+
+    #[test]
+    fn refuse_downgrade() {
+        let mut deps = mock_dependencies(&[]);
+        set_contract_version(&mut deps.storage, "crates.io:vesting", "99.0.0").unwrap();
+
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Migration(MigrationError::CannotMigrateVersion { previous_version: "99.0.0".to_string() })
+        );
+    }
 }
@@ -1,5 +1,6 @@
 use cosmwasm_std::StdError;
 use cw0::PaymentError;
+use ownership::{MigrationError, OwnershipError};
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -10,6 +11,12 @@ pub enum ContractError {
     #[error("{0}")]
     Payment(#[from] PaymentError),
 
+    #[error("{0}")]
+    Ownership(#[from] OwnershipError),
+
+    #[error("{0}")]
+    Migration(#[from] MigrationError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
@@ -21,7 +28,4 @@ pub enum ContractError {
 
     #[error("Invalid fee amount")]
     InvalidFeeAmount {},
-
-    #[error("Cannot migrate from different contract type: {previous_contract}")]
-    CannotMigrate { previous_contract: String },
 }
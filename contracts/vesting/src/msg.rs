@@ -1,4 +1,5 @@
 use cosmwasm_std::Uint128;
+use cw0::Expiration;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use crate::state::{FeeConfig, Vesting};
@@ -16,11 +17,16 @@ pub struct InstantiateMsg {
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
     UpdateConfig {
-        owner: Option<String>,
         name: Option<String>,
         fee_config: Option<Vec<FeeConfig>>,
         vesting: Option<Vesting>,
     },
+    ProposeNewOwner {
+        owner: String,
+        expiry: Option<Expiration>,
+    },
+    AcceptOwnership {},
+    CancelOwnershipProposal {},
     Claim {},
     RegisterMembers (
         Vec<RegisterMemberItem>
@@ -42,6 +48,7 @@ pub struct MigrateMsg {}
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Config {},
+    PendingOwner {},
     State {},
     Member {
         address: String